@@ -0,0 +1,219 @@
+//A copy of the recursive AVL tree this crate started with (commit d5a0a34), so the benchmarks can
+//compare the current trees with it. Every node stores a u32 height, every insertion and removal moves
+//the boxes on its search path out of the tree and back in and updates every height on the way up.
+
+use std::cmp;
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+
+#[derive(Clone)]
+pub struct Node<K:Ord,D> {
+    key: K,
+    data: D,
+    height: u32,
+    left: Option<Box<Node<K,D>>>,
+    right:Option<Box<Node<K,D>>>,
+}
+
+impl<K:Ord, D> Node<K,D> {
+    pub fn new(key: K, data: D) -> Node<K,D>{
+        Node::<K,D>{key, data, height: 1, left: None, right: None}
+    }
+}
+
+fn height<K:Ord,D>(node: &Option<Box<Node<K,D>>>) -> u32  {
+    node.as_ref().map_or(0, |succ| succ.height)
+}
+
+/// Perform a single right rotation on this (sub) tree
+fn rotate_right<K:Ord,D>(mut root: Box<Node<K,D>>) -> Box<Node<K,D>>{
+    let mut new_root_box = root.left.take().expect("AVL broken");
+    root.left = new_root_box.right.take();
+    update_height(&mut root);
+    new_root_box.right = Some(root);
+    update_height(&mut new_root_box);
+    new_root_box
+}
+
+/// Perform a single left rotation on this (sub) tree
+fn rotate_left<K:Ord,D>(mut root: Box<Node<K,D>>) -> Box<Node<K,D>>{
+    let mut new_root_box = root.right.take().expect("AVL broken");
+    root.right = new_root_box.left.take();
+    update_height(&mut root);
+    new_root_box.left = Some(root);
+    update_height(&mut new_root_box);
+    new_root_box
+}
+
+/// Performs a rotation that counteracts the fact that the left successor is too high
+fn rotate_left_successor<K:Ord,D>(mut root: Box<Node<K,D>>) -> Box<Node<K,D>> {
+    let left = root.left.take().expect("AVL broken");
+    if height(&left.left) < height(&left.right) {
+        let rotated = rotate_left(left);
+        root.left = Some(rotated);
+        update_height(&mut root);
+    }
+    else{
+        root.left = Some(left);
+    }
+    rotate_right(root)
+}
+
+/// Performs a rotation that counteracts the fact that the right successor is too high
+fn rotate_right_successor<K:Ord,D>(mut root: Box<Node<K,D>>) -> Box<Node<K,D>> {
+    let right = root.right.take().expect("AVL broken");
+    if height(&right.left) > height(&right.right) {
+        let rotated = rotate_right(right);
+        root.right = Some(rotated);
+        update_height(&mut root);
+    }
+    else {
+        root.right = Some(right)
+    }
+    rotate_left(root)
+}
+
+fn diff_of_successors_height<K:Ord,D>(root: &Node<K,D>) -> i32 {
+    let l = height(&root.left);
+    let r = height(&root.right);
+    (l as i32) - (r as i32)
+}
+
+/// Apply all necessary rotations on root.
+fn rotate_if_necessary<K:Ord,D>(root: Box<Node<K,D>>) -> Box<Node<K,D>> {
+    let diff  = diff_of_successors_height(&root);
+    if (-1..=1).contains(&diff) {return root}
+    match diff{
+        2 => rotate_left_successor::<K,D>(root),
+        -2 => rotate_right_successor::<K,D>(root),
+        _ => unreachable!()
+    }
+}
+
+/// update the cached height of root. To call this function make sure that the cached values of
+/// both children of root ar up to date.
+fn update_height<K:Ord,D>(root: &mut Node<K,D>){
+    root.height = cmp::max( height(&root.left), height(&root.right) )+1;
+}
+
+/// recursively insert the (key,data) pair into the given optional succesor and return its new
+/// value
+fn insert_in_successor<K:Ord,D>(key: K, data: D, successor: Option<Box<Node<K,D>>>)->Option<Box<Node<K,D>>> {
+            Some(match successor {
+                Some(succ) => insert(key, data, succ),
+                None =>Box::new(Node::new(key, data))
+            })
+}
+
+/// Inserts the given data under the key in the tree root. It will replace old data stored
+/// under this key if it was allready used in the tree. The resulting tree will be returned (its
+/// root may now differ due to rotations, thus the old root is moved into the function)
+pub fn insert<K:Ord,D>(key: K, data: D, mut root: Box<Node<K,D>>) -> Box<Node<K,D>>{
+    match root.key.cmp(&key) {
+        Ordering::Equal => { root.data  = data; return root },
+        Ordering::Less =>    root.right = insert_in_successor(key, data, root.right.take()),
+        Ordering::Greater => root.left  = insert_in_successor(key,data, root.left.take())
+    }
+    update_height(&mut *root);
+    rotate_if_necessary(root)
+}
+
+//will update_heights and rotate the node if necessary, returns the rotated node
+fn updated_node<K:Ord,D>(mut root: Box<Node<K,D>>) -> Box<Node<K,D>> {
+    update_height(&mut root);
+    rotate_if_necessary(root)
+}
+
+//Performs recursive `drop_and_get_min` if a left  since a successor is available
+#[allow(clippy::type_complexity)]
+fn drop_min_from_left<K:Ord,D>(mut root : Box<Node<K,D>>, left: Box<Node<K,D>>) -> (Option<Box<Node<K,D>>>,Box<Node<K,D>>) {
+    let (new_left, min) =  drop_min(left);
+    root.left = new_left;
+    (Some(updated_node(root)),min)
+}
+
+//Finds the minimal value below root and returns a new (optional) tree where the minimal value has been
+//removed and the (optional) minimal node as tuple (new_tree, min);
+#[allow(clippy::type_complexity)]
+fn drop_min<K:Ord,D>(mut root: Box<Node<K,D>>) -> (Option<Box<Node<K,D>>>, Box<Node<K,D>>) {
+    match root.left.take() {
+        Some(left) => drop_min_from_left(root, left),
+        None => (root.right.take(), root)
+    }
+}
+
+//Return a new AVL tree, as the combination of two subtrees with max(l) <= min(r)
+fn combine_two_subtrees<K:Ord,D>(l: Box<Node<K,D>>, r: Box<Node<K,D>>) -> Box<Node<K,D>>{
+    let (remaining_tree, min) = drop_min(r);
+    let mut new_root = min;
+    new_root.left = Some(l);
+    new_root.right = remaining_tree;
+    updated_node(new_root)
+}
+
+//Return a new AVL tree, where the root has been removed
+#[allow(clippy::boxed_local)]
+fn delete_root<K:Ord,D>(mut root: Box<Node<K,D>>) -> Option<Box<Node<K,D>>> {
+    match ( root.left.take(), root.right.take() ) {
+        ( None,     None)    => None,
+        ( Some(l),  None)    => Some(l),
+        ( None,     Some(r)) => Some(r),
+        ( Some(l),  Some(r)) => Some(combine_two_subtrees(l,r))
+    }
+}
+
+// will delete `key` from the tree `root`. Returns either `Some` tree or if the resilting tree is
+// empty: None.
+pub fn delete<K:Ord,D>(key: &K, mut root: Box<Node<K,D>>) -> Option<Box<Node<K,D>>>{
+    match root.key.cmp(key){
+        Ordering::Equal =>  return delete_root(root),
+        Ordering::Less => {
+            if let Some(succ) = root.right.take() {
+                root.right = delete(key, succ);
+                return Some(updated_node(root))
+            }
+        },
+        Ordering::Greater => {
+            if let Some(succ) = root.left.take() {
+                root.left =  delete(key, succ);
+                return Some(updated_node(root))
+            }
+        }
+    }
+    Some(root)
+}
+
+//the tree of the first version, which only knew its root
+#[derive(Clone)]
+pub struct AVLTree<K:Ord,D> {
+    pub root: Option<Box<Node<K,D>>>
+}
+
+impl <K:Ord,D> AVLTree<K,D>{
+    pub fn insert(&mut self, key: K, data: D) {
+        match self.root.take() {
+            Some(box_to_node) => self.root = Some(insert::<K,D>(key, data, box_to_node)),
+            None => self.root = Some(Box::new(Node::new(key,data))),
+        }
+    }
+
+    pub fn delete(&mut self, key: &K) {
+        if let Some(box_to_node) = self.root.take() {
+            self.root = delete(key, box_to_node);
+        }
+    }
+
+    pub fn empty(&self) -> bool {
+        self.root.is_none()
+    }
+}
+
+impl<K:Ord,D> FromIterator<(K,D)> for AVLTree<K,D> {
+    fn from_iter<I: IntoIterator<Item=(K,D)>>(pairs: I) -> AVLTree<K,D> {
+        let mut tree = AVLTree{root: None};
+        for (key, data) in pairs {
+            tree.insert(key, data);
+        }
+        tree
+    }
+}
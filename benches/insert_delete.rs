@@ -0,0 +1,189 @@
+extern crate avl_tree;
+
+#[allow(dead_code)]
+mod baseline;
+
+use std::iter::FromIterator;
+use std::time::{Duration, Instant};
+
+//Insertions walk down the search path in a loop and rebalance in place, the first version of this
+//crate (vendored in baseline) recursed down and moved every box on the path out of the tree and back
+//in. Removals still recurse along the search path, but they also work in place and stop rebalancing
+//at the first node whose rank does not change. Every case of run_all is measured on the baseline as
+//well, the other trees print their time relative to it.
+
+//the insertions and removals measured on all tree types, including the baseline
+trait BenchTree: Clone + FromIterator<(u64,u64)> {
+    fn new() -> Self;
+    fn insert(&mut self, key: u64, data: u64);
+    fn delete(&mut self, key: &u64);
+}
+
+//the operations the baseline does not offer
+trait CurrentTree: BenchTree {
+    fn push_back(&mut self, key: u64, data: u64);
+    fn count_forward(&self) -> usize;
+    fn count_reversed(&self) -> usize;
+}
+
+impl BenchTree for baseline::AVLTree<u64,u64> {
+    fn new() -> Self { baseline::AVLTree{root: None} }
+    fn insert(&mut self, key: u64, data: u64) { self.insert(key, data) }
+    fn delete(&mut self, key: &u64) { self.delete(key) }
+}
+
+impl<P: avl_tree::BalancePolicy + Clone> BenchTree for avl_tree::AVLTree<u64,u64,P> {
+    fn new() -> Self { avl_tree::AVLTree::default() }
+    fn insert(&mut self, key: u64, data: u64) { self.insert(key, data) }
+    fn delete(&mut self, key: &u64) { self.delete(key); }
+}
+
+impl<P: avl_tree::BalancePolicy + Clone> CurrentTree for avl_tree::AVLTree<u64,u64,P> {
+    fn push_back(&mut self, key: u64, data: u64) { self.push_back(key, data).unwrap() }
+    fn count_forward(&self) -> usize { self.iter().count() }
+    fn count_reversed(&self) -> usize { self.iter().rev().count() }
+}
+
+impl BenchTree for avl_tree::ArenaAVLTree<u64,u64> {
+    fn new() -> Self { avl_tree::ArenaAVLTree::new() }
+    fn insert(&mut self, key: u64, data: u64) { self.insert(key, data) }
+    fn delete(&mut self, key: &u64) { self.delete(key); }
+}
+
+impl CurrentTree for avl_tree::ArenaAVLTree<u64,u64> {
+    fn push_back(&mut self, key: u64, data: u64) { self.push_back(key, data).unwrap() }
+    fn count_forward(&self) -> usize { self.iter().count() }
+    fn count_reversed(&self) -> usize { self.iter().rev().count() }
+}
+
+//xorshift, so every run works on the same keys
+fn pseudo_random_keys(count: usize) -> Vec<u64> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    (0..count).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    }).collect()
+}
+
+//runs `run` on a fresh tree from `setup` a few times and prints the best time per operation, and
+//how it compares to the time of the baseline if there is one. Returns the best time per operation.
+fn measure<T, S: FnMut() -> T, F: FnMut(&mut T)>(name: &str, count: usize, baseline: Option<f64>, mut setup: S, mut run: F) -> f64 {
+    let mut best = Duration::MAX;
+    for _ in 0..5 {
+        let mut tree = setup();
+        let start = Instant::now();
+        run(&mut tree);
+        best = best.min(start.elapsed());
+    }
+    let per_op = best.as_secs_f64() * 1e9 / count as f64;
+    let compared = match baseline {
+        Some(baseline) => format!("{:>6.2}x the baseline", per_op / baseline),
+        None => String::new()
+    };
+    println!("{:<44} {:>8.1} ns/op   {}", name, per_op, compared);
+    per_op
+}
+
+//measures the insertions and removals on a tree type. Returns the times per operation, which are
+//compared with those of the baseline, if it was measured before.
+fn run_all<Tree: BenchTree>(prefix: &str, keys: &[u64], baseline: Option<&[f64]>) -> Vec<f64> {
+    let count = keys.len();
+    let full: Tree = keys.iter().map(|k| (*k, *k)).collect();
+    let name = |case: &str| format!("{}{}", prefix, case);
+    let base = |case: usize| baseline.map(|times| times[case]);
+
+    vec![
+        measure(&name("insert random"), count, base(0), Tree::new, |t| {
+            for k in keys.iter() {
+                t.insert(*k, *k);
+            }
+        }),
+        measure(&name("insert ascending"), count, base(1), Tree::new, |t| {
+            for k in 0..count as u64 {
+                t.insert(k, k);
+            }
+        }),
+        measure(&name("insert existing"), count, base(2), || full.clone(), |t| {
+            for k in keys.iter() {
+                t.insert(*k, 0);
+            }
+        }),
+        measure(&name("delete random"), count, base(3), || full.clone(), |t| {
+            for k in keys.iter() {
+                t.delete(k);
+            }
+        }),
+        measure(&name("delete missing"), count, base(4), || full.clone(), |t| {
+            for k in keys.iter() {
+                t.delete(&(k ^ 1));
+            }
+        }),
+        measure(&name("insert and delete"), count, base(5), || full.clone(), |t| {
+            for k in keys.iter() {
+                t.delete(k);
+                t.insert(k ^ 1, 0);
+            }
+        }),
+    ]
+}
+
+//measures the operations the baseline does not offer
+fn run_current<Tree: CurrentTree>(prefix: &str, keys: &[u64]) {
+    let count = keys.len();
+    let full: Tree = keys.iter().map(|k| (*k, *k)).collect();
+    let name = |case: &str| format!("{}{}", prefix, case);
+
+    measure(&name("push_back ascending"), count, None, Tree::new, |t| {
+        for k in 0..count as u64 {
+            t.push_back(k, k);
+        }
+    });
+    measure(&name("iterate"), count, None, || full.clone(), |t| {
+        assert_eq!(t.count_forward(), count);
+    });
+    measure(&name("iterate reversed"), count, None, || full.clone(), |t| {
+        assert_eq!(t.count_reversed(), count);
+    });
+}
+
+//appends ascending keys with insert and push_back, the keys are strings so that comparing them is
+//not as cheap as following a pointer
+fn run_appends<P: avl_tree::BalancePolicy>(prefix: &str, count: usize) {
+    let keys: Vec<String> = (0..count).map(|k| format!("event-{:012}", k)).collect();
+    let name = |case: &str| format!("{}{}", prefix, case);
+
+    measure(&name("insert ascending strings"), count, None, avl_tree::AVLTree::<String,u64,P>::default, |t| {
+        for k in keys.iter() {
+            t.insert(k.clone(), 0);
+        }
+    });
+    measure(&name("push_back ascending strings"), count, None, avl_tree::AVLTree::<String,u64,P>::default, |t| {
+        for k in keys.iter() {
+            t.push_back(k.clone(), 0).unwrap();
+        }
+    });
+    measure(&name("finger push_back ascending strings"), count, None, avl_tree::AVLTree::<String,u64,P>::default, |t| {
+        let mut finger = t.finger();
+        for k in keys.iter() {
+            finger.push_back(k.clone(), 0).unwrap();
+        }
+    });
+}
+
+fn main() {
+    let keys = pseudo_random_keys(200_000);
+    let baseline = run_all::<baseline::AVLTree<u64,u64>>("baseline ", &keys, None);
+    run_all::<avl_tree::AVLTree<u64,u64>>("", &keys, Some(&baseline));
+    run_all::<avl_tree::AVLTree<u64,u64,avl_tree::Wavl>>("wavl ", &keys, Some(&baseline));
+    run_all::<avl_tree::AVLTree<u64,u64,avl_tree::WeightBalanced>>("weight balanced ", &keys, Some(&baseline));
+    run_all::<avl_tree::ArenaAVLTree<u64,u64>>("arena ", &keys, Some(&baseline));
+    run_current::<avl_tree::AVLTree<u64,u64>>("", &keys);
+    run_current::<avl_tree::AVLTree<u64,u64,avl_tree::Wavl>>("wavl ", &keys);
+    run_current::<avl_tree::AVLTree<u64,u64,avl_tree::WeightBalanced>>("weight balanced ", &keys);
+    run_current::<avl_tree::ArenaAVLTree<u64,u64>>("arena ", &keys);
+    run_appends::<avl_tree::Avl>("", keys.len());
+    run_appends::<avl_tree::Wavl>("wavl ", keys.len());
+    run_appends::<avl_tree::WeightBalanced>("weight balanced ", keys.len());
+}
@@ -0,0 +1,84 @@
+extern crate avl_tree;
+
+#[allow(dead_code)]
+mod baseline;
+
+use std::fs;
+use std::iter::FromIterator;
+
+const COUNT: u64 = 500_000;
+
+//A node holds key, value and two pointers, besides them the first version of this crate stored a
+//u32 height. Rank balanced trees (Avl, Wavl) store a single byte of rank differences instead, which
+//saves a word on 64 bit targets when key and value leave less than four bytes of padding: a
+//u32 -> u16 node takes 24 B instead of 32 B, while u64 -> u32 and u64 -> u64 nodes keep their 32 B
+//and 40 B. WeightBalanced trees store a usize with the size of the subtree, which makes a
+//u64 -> u32 node 40 B. The allocator of glibc adds an 8 B header and rounds chunks up to 16 B
+//steps, so 24 B nodes take 32 B per pair and 32 and 40 B nodes both take 48 B per pair.
+
+//the resident memory of this process in bytes, read from /proc on linux
+fn resident_bytes() -> Option<u64> {
+    let statm = fs::read_to_string("/proc/self/statm").ok()?;
+    let pages : u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * 4096)
+}
+
+//xorshift, so every run works on the same keys
+fn pseudo_random_keys() -> impl Iterator<Item=u64> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    (0..COUNT).map(move |_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    })
+}
+
+//builds a tree of COUNT pairs and prints how much memory it takes per pair, and how much more or
+//less than the baseline tree of the same pairs if that was measured before. The tree is returned
+//and kept alive, so the next measurement does not reuse its freed memory.
+fn measure<K, D, T: FromIterator<(K,D)>, F: Fn(u64) -> (K, D)>(name: &str, baseline: Option<f64>, pair: F) -> (T, Option<f64>) {
+    let before = resident_bytes();
+    let tree : T = pseudo_random_keys().map(pair).collect();
+    let payload = (std::mem::size_of::<K>() + std::mem::size_of::<D>()) as f64;
+    let per_pair = match (before, resident_bytes()) {
+        (Some(before), Some(after)) => (after - before) as f64 / COUNT as f64,
+        _ => {
+            println!("{:<28} payload {:>3} B   resident memory is only measured on linux", name, payload);
+            return (tree, None)
+        }
+    };
+    let compared = match baseline {
+        Some(baseline) => format!("{:>+6.1} B/pair against the baseline", per_pair - baseline),
+        None => String::new()
+    };
+    println!("{:<28} payload {:>3} B   measured {:>5.1} B/pair   overhead {:>5.1} B/pair   {}",
+             name, payload, per_pair, per_pair - payload, compared);
+    (tree, Some(per_pair))
+}
+
+type Baseline<K,D> = baseline::AVLTree<K,D>;
+type Tree<K,D> = avl_tree::AVLTree<K,D>;
+type WeightTree<K,D> = avl_tree::AVLTree<K,D,avl_tree::WeightBalanced>;
+type ArenaTree<K,D> = avl_tree::ArenaAVLTree<K,D>;
+
+fn main() {
+    let (a, base) : (Baseline<u32,u16>, _) = measure("baseline u32 -> u16", None, |k| (k as u32, k as u16));
+    let (b, _) : (Tree<u32,u16>, _) = measure("Avl u32 -> u16", base, |k| (k as u32, k as u16));
+    let (c, _) : (WeightTree<u32,u16>, _) = measure("WeightBalanced u32 -> u16", base, |k| (k as u32, k as u16));
+    //the arena grows by doubling, so up to half of its capacity may be unused
+    let (d, _) : (ArenaTree<u32,u16>, _) = measure("arena u32 -> u16", base, |k| (k as u32, k as u16));
+    assert!(!a.empty() && b.len() + c.len() + d.len() > 0);
+
+    let (a, base) : (Baseline<u64,u32>, _) = measure("baseline u64 -> u32", None, |k| (k, k as u32));
+    let (b, _) : (Tree<u64,u32>, _) = measure("Avl u64 -> u32", base, |k| (k, k as u32));
+    let (c, _) : (WeightTree<u64,u32>, _) = measure("WeightBalanced u64 -> u32", base, |k| (k, k as u32));
+    let (d, _) : (ArenaTree<u64,u32>, _) = measure("arena u64 -> u32", base, |k| (k, k as u32));
+    assert!(!a.empty() && b.len() + c.len() + d.len() > 0);
+
+    let (a, base) : (Baseline<u64,u64>, _) = measure("baseline u64 -> u64", None, |k| (k, k));
+    let (b, _) : (Tree<u64,u64>, _) = measure("Avl u64 -> u64", base, |k| (k, k));
+    let (c, _) : (WeightTree<u64,u64>, _) = measure("WeightBalanced u64 -> u64", base, |k| (k, k));
+    let (d, _) : (ArenaTree<u64,u64>, _) = measure("arena u64 -> u64", base, |k| (k, k));
+    assert!(!a.empty() && b.len() + c.len() + d.len() > 0);
+}
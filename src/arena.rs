@@ -0,0 +1,2066 @@
+use arena_cursor::{self, ArenaCursor, ArenaCursorMut};
+use arena_entry::{self, ArenaEntry, ArenaOccupiedEntry};
+use balance::{Avl, BalancePolicy};
+use error::{UnsortedError, PushBackError};
+use node::{self, is_above_lower_bound, is_below_upper_bound, Rebalance, SearchPath, Store};
+use tree::merge_sorted;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::{FromIterator, Map};
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Bound, Index, RangeBounds};
+
+//marks a missing successor, an empty tree or the end of the free list
+pub const NIL: u32 = u32::MAX;
+
+//the iterators returned by values_mut, into_keys and into_values
+type ValuesMut<'a,K,D,P> = Map<ArenaIterMut<'a,K,D,P>, fn((&'a K,&'a mut D)) -> &'a mut D>;
+type IntoKeys<K,D,P> = Map<ArenaIntoIter<K,D,P>, fn((K,D)) -> K>;
+type IntoValues<K,D,P> = Map<ArenaIntoIter<K,D,P>, fn((K,D)) -> D>;
+
+//a node stores the balance information of the policy like a boxed node. Under Avl and Wavl these are
+//the rank differences, so a slot can store whether it is vacant in the values they never take.
+#[derive(Clone)]
+struct ArenaNode<K,D,P:Rebalance> {
+    key: K,
+    data: D,
+    left: u32,
+    right: u32,
+    meta: P::Meta,
+}
+
+//a slot of the arena either holds a node or links to the next vacant slot
+#[derive(Clone)]
+enum Slot<K,D,P:Rebalance> {
+    Occupied(ArenaNode<K,D,P>),
+    Vacant(u32),
+}
+
+//an optional subtree given by the index of its root, and its rank
+type Subtree = (Option<u32>, usize);
+
+//returns the subtree at index, or None if index is NIL
+fn subtree(index: u32) -> Option<u32> {
+    if index == NIL { None } else { Some(index) }
+}
+
+//The slots of a tree together with the list of the vacant ones. It stores the nodes for the balancing
+//policies and the operations that split and join trees, a node is the index of its slot and owns the
+//subtrees its successors link to.
+#[derive(Clone)]
+struct Arena<K,D,P:Rebalance> {
+    slots: Vec<Slot<K,D,P>>,
+    free: u32,
+}
+
+impl<K,D,P:Rebalance> Arena<K,D,P> {
+
+    fn node(&self, index: u32) -> &ArenaNode<K,D,P> {
+        match self.slots[index as usize] {
+            Slot::Occupied(ref node) => node,
+            Slot::Vacant(_) => panic!("AVL broken")
+        }
+    }
+
+    fn node_mut(&mut self, index: u32) -> &mut ArenaNode<K,D,P> {
+        match self.slots[index as usize] {
+            Slot::Occupied(ref mut node) => node,
+            Slot::Vacant(_) => panic!("AVL broken")
+        }
+    }
+
+    //returns the successor of the node at index on `side` (-1 for left, 1 for right), or NIL
+    fn successor(&self, index: u32, side: i8) -> u32 {
+        let node = self.node(index);
+        if side > 0 { node.right } else { node.left }
+    }
+
+    fn successor_mut(&mut self, index: u32, side: i8) -> &mut u32 {
+        let node = self.node_mut(index);
+        if side > 0 { &mut node.right } else { &mut node.left }
+    }
+}
+
+impl<K:Ord,D,P:Rebalance> Store<K,D,P> for Arena<K,D,P> {
+    type Node = u32;
+
+    fn key<'a>(&'a self, node: &'a u32) -> &'a K {
+        &self.node(*node).key
+    }
+
+    fn meta(&self, node: &u32) -> P::Meta {
+        self.node(*node).meta
+    }
+
+    fn set_meta(&mut self, node: &mut u32, meta: P::Meta) {
+        self.node_mut(*node).meta = meta;
+    }
+
+    fn successor<'a>(&'a self, node: &'a u32, side: i8) -> Option<&'a u32> {
+        let node = self.node(*node);
+        let successor = if side > 0 { &node.right } else { &node.left };
+        if *successor == NIL { None } else { Some(successor) }
+    }
+
+    fn take_successor(&mut self, node: &mut u32, side: i8) -> Option<u32> {
+        subtree(mem::replace(self.successor_mut(*node, side), NIL))
+    }
+
+    fn set_successor(&mut self, node: &mut u32, side: i8, successor: Option<u32>) {
+        *self.successor_mut(*node, side) = successor.unwrap_or(NIL);
+    }
+
+    //stores the new node in the first vacant slot, or at the end of the arena if there is none
+    fn create(&mut self, key: K, data: D) -> u32 {
+        let node = Slot::Occupied(ArenaNode{key, data, left: NIL, right: NIL, meta: P::LEAF});
+        if self.free != NIL {
+            let index = self.free;
+            match mem::replace(&mut self.slots[index as usize], node) {
+                Slot::Vacant(next) => self.free = next,
+                Slot::Occupied(_) => panic!("AVL broken")
+            }
+            return index
+        }
+        assert!(self.slots.len() < NIL as usize, "an ArenaAVLTree can not hold more than {} nodes", NIL - 1);
+        self.slots.push(node);
+        (self.slots.len() - 1) as u32
+    }
+
+    //makes the slot vacant and puts it at the front of the free list
+    fn release(&mut self, index: u32) -> (K, D) {
+        match mem::replace(&mut self.slots[index as usize], Slot::Vacant(self.free)) {
+            Slot::Occupied(node) => {
+                self.free = index;
+                (node.key, node.data)
+            },
+            Slot::Vacant(_) => panic!("AVL broken")
+        }
+    }
+
+    fn replace_pair(&mut self, node: &mut u32, key: K, data: D) -> (K, D) {
+        let node = self.node_mut(*node);
+        (mem::replace(&mut node.key, key), mem::replace(&mut node.data, data))
+    }
+
+    fn discard(&mut self, tree: Option<u32>) {
+        if let Some(mut index) = tree {
+            let (left, right) = self.take_successors(&mut index);
+            self.discard(left);
+            self.discard(right);
+            self.release(index);
+        }
+    }
+}
+
+/// An AVL tree with the same map API as `AVLTree`, whose nodes live in a single `Vec` and link to
+/// each other by u32 indices. Removed nodes leave a vacant slot behind that is reused by the next
+/// insert, so a tree whose size stays about the same does not call the allocator at all.
+/// `shrink_to_fit` moves all nodes to the front of the arena in the order of their keys and frees
+/// the unused capacity. The arena holds at most `u32::MAX - 1` nodes. Like `AVLTree` it is balanced
+/// by the policy `P`, whose code rebalances the nodes of both trees.
+///
+/// The operations that split and join trees have different costs than those of `AVLTree`, because
+/// two trees do not share an arena. `split_off`, `split` and `delete_range` cut the tree in O(log n)
+/// like `AVLTree`, but then move every pair of the returned tree into a new arena, so they take time
+/// linear in the size of the returned tree. `append`, `union_with`, `intersection_with` and
+/// `symmetric_difference` first move all pairs of the other tree into this arena, which takes time
+/// linear in the size of the other tree, so `append` does not join two trees in O(log n) like
+/// `AVLTree::append`.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::with_capacity(100);
+/// for key in 0..100 {
+///     t.insert(key, 0);
+/// }
+/// for key in 0..100 {
+///     t.delete(&key);
+///     t.insert(key + 100, 1);
+/// }
+/// assert_eq!(t.capacity(), 100);
+/// assert_eq!(t.first_key_value(), Some((&100,&1)));
+/// ```
+#[derive(Clone)]
+pub struct ArenaAVLTree<K:Ord,D,P:BalancePolicy=Avl> {
+    arena: Arena<K,D,P>,
+    pub(crate) root: u32,
+    len: usize,
+}
+
+impl <K:Ord,D> ArenaAVLTree<K,D>{
+
+/// This function will construct a new empty ArenaAVLTree.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// ```
+    pub fn new() -> ArenaAVLTree<K,D>{
+        ArenaAVLTree::with_policy(Avl)
+    }
+}
+
+impl <K:Ord,D,P:BalancePolicy> ArenaAVLTree<K,D,P>{
+
+/// This function will construct a new empty tree that is balanced by the given policy, like
+/// `AVLTree::with_policy`. The nodes store the same balance information as those of an `AVLTree`.
+/// # Examples
+/// ```
+/// use avl_tree::{ArenaAVLTree, Wavl, WeightBalanced};
+/// let mut t = ArenaAVLTree::with_policy(Wavl);
+/// t.insert(2,25);
+/// assert_eq!(t.get(&2), Some(&25));
+///
+/// let t : ArenaAVLTree<u64,i32,WeightBalanced> = (0..100).map(|k| (k, 0)).collect();
+/// assert_eq!(t.len(), 100);
+/// ```
+    pub fn with_policy(_policy: P) -> ArenaAVLTree<K,D,P>{
+        ArenaAVLTree::with_capacity(0)
+    }
+
+/// This function will construct a new empty ArenaAVLTree with room for `capacity` nodes, which can
+/// be inserted without reallocating the arena.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::with_capacity(10);
+/// assert!(t.capacity() >= 10);
+/// ```
+    pub fn with_capacity(capacity: usize) -> ArenaAVLTree<K,D,P>{
+        ArenaAVLTree{arena: Arena{slots: Vec::with_capacity(capacity), free: NIL}, root: NIL, len: 0}
+    }
+
+/// This function will build a tree from pairs that are sorted by strictly ascending keys in linear
+/// time. If a key is not bigger than the key before it, an error with its position is returned.
+/// # Examples
+/// ```
+/// let t : avl_tree::ArenaAVLTree<u64,u64> = avl_tree::ArenaAVLTree::from_sorted_iter((0..100).map(|k| (k, k*2))).unwrap();
+/// assert_eq!(t.get(&21), Some(&42));
+/// assert_eq!(t.capacity(), 100);
+///
+/// let unsorted = avl_tree::ArenaAVLTree::<_,_,avl_tree::Wavl>::from_sorted_iter(vec![(1,'a'), (3,'b'), (2,'c')]);
+/// assert_eq!(unsorted.unwrap_err().position(), 2);
+/// ```
+    pub fn from_sorted_iter<I: IntoIterator<Item=(K,D)>>(pairs: I) -> Result<ArenaAVLTree<K,D,P>, UnsortedError> {
+        let mut sorted : Vec<(K,D)> = Vec::new();
+        for (key, data) in pairs {
+            if sorted.last().is_some_and(|last| last.0 >= key) {
+                return Err(UnsortedError::new(sorted.len()))
+            }
+            sorted.push((key, data));
+        }
+        Ok(ArenaAVLTree::from_sorted_iter_unchecked(sorted))
+    }
+
+/// This function will build a tree from pairs that are sorted by strictly ascending keys in linear
+/// time, without checking the order of the keys. If the keys are not strictly ascending, the
+/// resulting tree is broken and lookups will miss keys.
+/// # Examples
+/// ```
+/// let t : avl_tree::ArenaAVLTree<u64,u64> = avl_tree::ArenaAVLTree::from_sorted_iter_unchecked((0..100).map(|k| (k, k*2)));
+/// assert_eq!(t.last_key_value(), Some((&99,&198)));
+/// ```
+    pub fn from_sorted_iter_unchecked<I: IntoIterator<Item=(K,D)>>(pairs: I) -> ArenaAVLTree<K,D,P> {
+        let sorted : Vec<(K,D)> = pairs.into_iter().collect();
+        let mut tree = ArenaAVLTree::with_capacity(sorted.len());
+        tree.len = sorted.len();
+        tree.root = tree.build_sorted(sorted).0.unwrap_or(NIL);
+        tree
+    }
+
+/// This function will return the number of nodes the tree can hold without reallocating the arena.
+/// Vacant slots left behind by removed nodes are counted as well.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.delete(&2);
+/// assert!(t.capacity() >= 1);
+/// ```
+    pub fn capacity(&self) -> usize {
+        self.arena.slots.capacity()
+    }
+
+/// This function will move all nodes to the front of the arena in the order of their keys, which
+/// drops the vacant slots and makes iterating cache friendly, and then frees the unused capacity.
+/// # Examples
+/// ```
+/// let mut t : avl_tree::ArenaAVLTree<u64,i32> = (0..100).map(|k| (k, 0)).collect();
+/// t.retain(|k, _| k % 10 == 0);
+/// t.shrink_to_fit();
+/// assert_eq!(t.capacity(), 10);
+/// assert_eq!(t.iter().map(|(k,_)| *k).collect::<Vec<u64>>(), (0..10).map(|k| k*10).collect::<Vec<u64>>());
+/// ```
+    pub fn shrink_to_fit(&mut self) {
+        let order : Vec<u32> = self.indices(Bound::Unbounded, Bound::Unbounded).collect();
+        let mut new_index = vec![NIL; self.arena.slots.len()];
+        for (position, index) in order.iter().enumerate() {
+            new_index[*index as usize] = position as u32;
+        }
+        let relink = |index: u32| if index == NIL { NIL } else { new_index[index as usize] };
+        let mut slots = Vec::with_capacity(order.len());
+        for index in order {
+            match mem::replace(&mut self.arena.slots[index as usize], Slot::Vacant(NIL)) {
+                Slot::Occupied(mut node) => {
+                    node.left = relink(node.left);
+                    node.right = relink(node.right);
+                    slots.push(Slot::Occupied(node));
+                },
+                Slot::Vacant(_) => panic!("AVL broken")
+            }
+        }
+        self.root = relink(self.root);
+        self.arena = Arena{slots, free: NIL};
+    }
+
+/// This function will insert the key,value pair into the tree. If the key was already part of the
+/// tree, its value is replaced. The node is stored in a vacant slot if there is one.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// assert_eq!(t.get(&2), Some(&25));
+/// t.insert(2,30);
+/// assert_eq!(t.get(&2), Some(&30));
+/// ```
+    pub fn insert(&mut self, key: K, data: D) {
+        match self.search_path(&key) {
+            (_, Some(index)) => *self.data_mut(index) = data,
+            (search, None) => { self.insert_at(key, data, search); }
+        }
+    }
+
+/// This function will insert the key,value pair as new maximum of the tree. If the key is not bigger
+/// than all keys of the tree, the pair is handed back in an error. The pair is appended along the
+/// right spine, so only the key of the maximal node is compared and nothing besides the new node is
+/// allocated.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// for key in 0..10 {
+///     t.push_back(key, 0).unwrap();
+/// }
+/// let error = t.push_back(5, 1).unwrap_err();
+/// assert_eq!(error.into_pair(), (5,1));
+/// assert_eq!(t.get(&5), Some(&0));
+/// ```
+    pub fn push_back(&mut self, key: K, data: D) -> Result<(), PushBackError<K,D>> {
+        match node::push_back_path::<P,_,_,_>(&self.arena, &key, self.root_node()) {
+            Some(search) => {
+                self.insert_at(key, data, search);
+                Ok(())
+            },
+            None => Err(PushBackError::new(key, data))
+        }
+    }
+
+/// This function will remove the key,value pair from the tree, doing nothing if the key is not
+/// part of the tree. Returns whether the key was part of the tree.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// assert!(t.delete(&2));
+/// assert!(t.empty());
+/// assert!(!t.delete(&3));
+/// ```
+    pub fn delete<Q:Ord+?Sized>(&mut self, key: &Q) -> bool where K: Borrow<Q> {
+        self.remove_entry(key).is_some()
+    }
+
+/// This function will remove the key,value pair from the tree and return the data of the removed
+/// pair, or None if the key is not part of the tree.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// assert_eq!(t.remove(&2), Some(25));
+/// assert_eq!(t.remove(&2), None);
+/// ```
+    pub fn remove<Q:Ord+?Sized>(&mut self, key: &Q) -> Option<D> where K: Borrow<Q> {
+        self.remove_entry(key).map(|(_,data)| data)
+    }
+
+/// This function will remove the key,value pair from the tree and return Some((key,data)) of the
+/// removed pair, or None if the key is not part of the tree.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// assert_eq!(t.remove_entry(&2), Some((2,25)));
+/// assert_eq!(t.remove_entry(&2), None);
+/// ```
+    pub fn remove_entry<Q:Ord+?Sized>(&mut self, key: &Q) -> Option<(K,D)> where K: Borrow<Q> {
+        let (search, found) = self.search_path(key);
+        found.map(|_| self.remove_at(search))
+    }
+
+/// This function will return the entry for the given key, which can be used to inspect, insert,
+/// modify or remove the data stored under it. The key is searched once, the entry keeps the path.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.entry(2).or_insert(25);
+/// assert_eq!(t.get(&2), Some(&25));
+/// *t.entry(2).or_insert(0) += 5;
+/// assert_eq!(t.get(&2), Some(&30));
+/// ```
+    pub fn entry(&mut self, key: K) -> ArenaEntry<'_,K,D,P> {
+        arena_entry::entry(self, key)
+    }
+
+/// This function will return the data stored under the given key or None if the key is not
+/// known.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// assert_eq!(t.get(&2), Some(&25));
+/// assert_eq!(t.get(&3), None);
+/// ```
+    pub fn get<Q:Ord+?Sized>(&self, key: &Q) -> Option<&D> where K: Borrow<Q> {
+        match self.find(key) {
+            NIL => None,
+            index => Some(self.pair(index).1)
+        }
+    }
+
+/// This function will return a mutable reference to the data stored under the given key or None if
+/// the key is not known.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// *t.get_mut(&2).unwrap() += 1;
+/// assert_eq!(t.get(&2), Some(&26));
+/// ```
+    pub fn get_mut<Q:Ord+?Sized>(&mut self, key: &Q) -> Option<&mut D> where K: Borrow<Q> {
+        match self.find(key) {
+            NIL => None,
+            index => Some(self.data_mut(index))
+        }
+    }
+
+/// This function will return the data stored under the given key or the default if the key is not
+/// known.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// assert_eq!(t.get_or(&2,&2000), &25);
+/// assert_eq!(t.get_or(&3,&2000), &2000);
+/// ```
+    pub fn get_or<'a, Q:Ord+?Sized>(&'a self, key: &Q, default: &'a D) -> &'a D where K: Borrow<Q> {
+        self.get(key).unwrap_or(default)
+    }
+
+/// This function will return true if the tree contains the given key, false otherwise
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// assert!(!t.contains(&3));
+/// assert!(t.contains(&2));
+/// ```
+    pub fn contains<Q:Ord+?Sized>(&self, key: &Q) -> bool where K: Borrow<Q> {
+        self.find(key) != NIL
+    }
+
+/// This function will return true if the tree is empty, false otherwise.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// assert!(t.empty());
+/// t.insert(2,25);
+/// assert!(!t.empty());
+/// ```
+    pub fn empty(&self) -> bool { self.root == NIL }
+
+/// This function will return true if the tree is empty, false otherwise. It is the same as `empty`.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// assert!(t.is_empty());
+/// ```
+    pub fn is_empty(&self) -> bool { self.empty() }
+
+/// This function will return the number of key,value pairs stored in the tree.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// assert_eq!(t.len(), 2);
+/// ```
+    pub fn len(&self) -> usize { self.len }
+
+/// This function will return the key/value pair with the smallest key in the tree, or None if the
+/// tree is empty. Like in `BTreeMap` it is not called `min`, which `Ord::min` would shadow.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// assert_eq!(t.first_key_value(), Some((&2,&25)));
+/// ```
+    pub fn first_key_value(&self) -> Option<(&K,&D)> {
+        self.pair_if_any(self.spine_end(self.root, -1))
+    }
+
+/// This function will return the key/value pair with the biggest key in the tree, or None if the
+/// tree is empty. Like in `BTreeMap` it is not called `max`, which `Ord::max` would shadow.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// assert_eq!(t.last_key_value(), Some((&3,&50)));
+/// ```
+    pub fn last_key_value(&self) -> Option<(&K,&D)> {
+        self.pair_if_any(self.spine_end(self.root, 1))
+    }
+
+/// This function will remove the key/value pair with the smallest key from the tree and return it,
+/// or None if the tree is empty.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// assert_eq!(t.pop_min(), Some((2,25)));
+/// assert_eq!(t.len(), 1);
+/// ```
+    pub fn pop_min(&mut self) -> Option<(K,D)> {
+        self.remove_end(-1)
+    }
+
+/// This function will remove the key/value pair with the biggest key from the tree and return it,
+/// or None if the tree is empty.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// assert_eq!(t.pop_max(), Some((3,50)));
+/// assert_eq!(t.len(), 1);
+/// ```
+    pub fn pop_max(&mut self) -> Option<(K,D)> {
+        self.remove_end(1)
+    }
+
+/// This function will return the entry of the smallest key in the tree, which can be used to modify
+/// or remove it in place, or None if the tree is empty.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// *t.first_entry().unwrap().get_mut() += 1;
+/// assert_eq!(t.get(&2), Some(&26));
+/// assert_eq!(t.first_entry().unwrap().remove(), 26);
+/// assert_eq!(t.first_key_value(), Some((&3,&50)));
+/// ```
+    pub fn first_entry(&mut self) -> Option<ArenaOccupiedEntry<'_,K,D,P>> {
+        arena_entry::spine_entry(self, -1)
+    }
+
+/// This function will return the entry of the biggest key in the tree, which can be used to modify
+/// or remove it in place, or None if the tree is empty.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// assert_eq!(t.last_entry().unwrap().key(), &3);
+/// assert_eq!(t.last_entry().unwrap().insert(60), 50);
+/// assert_eq!(t.get(&3), Some(&60));
+/// ```
+    pub fn last_entry(&mut self) -> Option<ArenaOccupiedEntry<'_,K,D,P>> {
+        arena_entry::spine_entry(self, 1)
+    }
+
+/// This function will return a cursor pointing at the pair with the smallest key above the given
+/// bound, or at the ghost position between the maximal and the minimal pair if there is no such key.
+/// The cursor can move back and forth from there.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::ArenaAVLTree<u64,i32> = (0..10).map(|k| (k*2, 0)).collect();
+/// let mut cursor = t.lower_bound_cursor(Bound::Excluded(&4));
+/// assert_eq!(cursor.key(), Some(&6));
+/// cursor.move_prev();
+/// assert_eq!(cursor.key(), Some(&4));
+/// assert_eq!(t.lower_bound_cursor(Bound::Unbounded).key(), Some(&0));
+/// assert_eq!(t.lower_bound_cursor(Bound::Included(&19)).key(), None);
+/// ```
+    pub fn lower_bound_cursor<'a, Q:Ord+?Sized>(&'a self, bound: Bound<&Q>) -> ArenaCursor<'a,K,D,P> where K: Borrow<Q> {
+        arena_cursor::lower_bound_cursor(self, bound)
+    }
+
+/// This function will return a cursor pointing at the pair with the biggest key below the given
+/// bound, or at the ghost position between the maximal and the minimal pair if there is no such key.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::ArenaAVLTree<u64,i32> = (0..10).map(|k| (k*2, 0)).collect();
+/// assert_eq!(t.upper_bound_cursor(Bound::Excluded(&4)).key(), Some(&2));
+/// assert_eq!(t.upper_bound_cursor(Bound::Included(&4)).key(), Some(&4));
+/// assert_eq!(t.upper_bound_cursor(Bound::Unbounded).key(), Some(&18));
+/// assert_eq!(t.upper_bound_cursor(Bound::Excluded(&0)).key(), None);
+/// ```
+    pub fn upper_bound_cursor<'a, Q:Ord+?Sized>(&'a self, bound: Bound<&Q>) -> ArenaCursor<'a,K,D,P> where K: Borrow<Q> {
+        arena_cursor::upper_bound_cursor(self, bound)
+    }
+
+/// This function will return a cursor like `lower_bound_cursor`, which can also modify values,
+/// remove pairs and insert new pairs next to its position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t : avl_tree::ArenaAVLTree<u64,i32> = (0..10).map(|k| (k*2, 0)).collect();
+/// {
+///     let mut cursor = t.lower_bound_cursor_mut(Bound::Excluded(&4));
+///     cursor.insert_before(5, 1).unwrap();
+///     *cursor.value_mut().unwrap() = 2;
+///     cursor.move_next();
+///     assert_eq!(cursor.remove_current(), Some((8,0)));
+/// }
+/// assert_eq!(t.range(4..=10).map(|(k,v)| (*k,*v)).collect::<Vec<_>>(), vec![(4,0), (5,1), (6,2), (10,0)]);
+/// ```
+    pub fn lower_bound_cursor_mut<'a, Q:Ord+?Sized>(&'a mut self, bound: Bound<&Q>) -> ArenaCursorMut<'a,K,D,P> where K: Borrow<Q> {
+        arena_cursor::lower_bound_cursor_mut(self, bound)
+    }
+
+/// This function will return a cursor like `upper_bound_cursor`, which can also modify values,
+/// remove pairs and insert new pairs next to its position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t : avl_tree::ArenaAVLTree<u64,i32> = (0..10).map(|k| (k*2, 0)).collect();
+/// {
+///     let mut cursor = t.upper_bound_cursor_mut(Bound::Unbounded);
+///     assert_eq!(cursor.remove_current(), Some((18,0)));
+///     assert_eq!(cursor.key(), None);
+///     assert!(cursor.insert_after(1, 1).is_err());
+///     cursor.insert_before(17, 1).unwrap();
+/// }
+/// assert_eq!(t.last_key_value(), Some((&17,&1)));
+/// ```
+    pub fn upper_bound_cursor_mut<'a, Q:Ord+?Sized>(&'a mut self, bound: Bound<&Q>) -> ArenaCursorMut<'a,K,D,P> where K: Borrow<Q> {
+        arena_cursor::upper_bound_cursor_mut(self, bound)
+    }
+
+/// This function will return the key/value pair with the biggest key that is smaller than or equal
+/// to the given key, or None if there is no such key.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(4,50);
+/// assert_eq!(t.floor(&3), Some((&2,&25)));
+/// assert_eq!(t.floor(&4), Some((&4,&50)));
+/// assert_eq!(t.floor(&1), None);
+/// ```
+    pub fn floor<Q:Ord+?Sized>(&self, key: &Q) -> Option<(&K,&D)> where K: Borrow<Q> {
+        self.pair_if_any(self.last_below(Bound::Included(key)))
+    }
+
+/// This function will return the key/value pair with the smallest key that is bigger than or equal
+/// to the given key, or None if there is no such key.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(4,50);
+/// assert_eq!(t.ceiling(&3), Some((&4,&50)));
+/// assert_eq!(t.ceiling(&5), None);
+/// ```
+    pub fn ceiling<Q:Ord+?Sized>(&self, key: &Q) -> Option<(&K,&D)> where K: Borrow<Q> {
+        self.pair_if_any(self.first_above(Bound::Included(key)))
+    }
+
+/// This function will return the key/value pair with the biggest key that is smaller than the
+/// given key, or None if there is no such key.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(4,50);
+/// assert_eq!(t.lower(&4), Some((&2,&25)));
+/// assert_eq!(t.lower(&2), None);
+/// ```
+    pub fn lower<Q:Ord+?Sized>(&self, key: &Q) -> Option<(&K,&D)> where K: Borrow<Q> {
+        self.pair_if_any(self.last_below(Bound::Excluded(key)))
+    }
+
+/// This function will return the key/value pair with the smallest key that is bigger than the
+/// given key, or None if there is no such key.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(4,50);
+/// assert_eq!(t.higher(&2), Some((&4,&50)));
+/// assert_eq!(t.higher(&4), None);
+/// ```
+    pub fn higher<Q:Ord+?Sized>(&self, key: &Q) -> Option<(&K,&D)> where K: Borrow<Q> {
+        self.pair_if_any(self.first_above(Bound::Excluded(key)))
+    }
+
+/// This function will return an iterator over all key/value pairs ordered by key, which can be
+/// consumed from both ends.
+/// # Examples
+/// ```
+/// let t : avl_tree::ArenaAVLTree<u64,i32> = (0..5).map(|k| (k, 0)).collect();
+/// assert_eq!(t.iter().map(|(k,_)| *k).collect::<Vec<u64>>(), vec![0,1,2,3,4]);
+/// assert_eq!(t.iter().rev().next(), Some((&4,&0)));
+/// ```
+    pub fn iter<'a>(&'a self) -> ArenaIter<'a,K,D,P>{
+        ArenaIter{tree: self, indices: self.indices::<K>(Bound::Unbounded, Bound::Unbounded)}
+    }
+
+/// This function will return an iterator over all key/value pairs within the given range, ordered by
+/// key.
+/// # Examples
+/// ```
+/// let t : avl_tree::ArenaAVLTree<u64,i32> = (0..50).map(|k| (k, 0)).collect();
+/// assert_eq!(t.range(32..=38).map(|(k,_)| *k).collect::<Vec<u64>>(), (32..39).collect::<Vec<u64>>());
+/// ```
+    pub fn range<'a, Q:Ord+?Sized, R:RangeBounds<Q>>(&'a self, range: R) -> ArenaIter<'a,K,D,P> where K: Borrow<Q> {
+        ArenaIter{tree: self, indices: self.indices(range.start_bound(), range.end_bound())}
+    }
+
+/// This function will return an iterator over all key/value pairs ordered by key, handing out
+/// mutable references to the values.
+/// # Examples
+/// ```
+/// let mut t : avl_tree::ArenaAVLTree<u64,i32> = (0..5).map(|k| (k, 0)).collect();
+/// for (key, value) in t.iter_mut() {
+///     *value = *key as i32;
+/// }
+/// assert_eq!(t.get(&3), Some(&3));
+/// ```
+    pub fn iter_mut<'a>(&'a mut self) -> ArenaIterMut<'a,K,D,P>{
+        self.range_mut::<K,_>(..)
+    }
+
+/// This function will return an iterator over all key/value pairs within the given range ordered by
+/// key, handing out mutable references to the values.
+/// # Examples
+/// ```
+/// let mut t : avl_tree::ArenaAVLTree<u64,i32> = (0..50).map(|k| (k, 0)).collect();
+/// for (_, value) in t.range_mut(10..20) {
+///     *value = 1;
+/// }
+/// assert_eq!(t.iter().filter(|(_,v)| **v == 1).count(), 10);
+/// ```
+    pub fn range_mut<'a, Q:Ord+?Sized, R:RangeBounds<Q>>(&'a mut self, range: R) -> ArenaIterMut<'a,K,D,P> where K: Borrow<Q> {
+        let walk = self.walk(range.start_bound(), range.end_bound());
+        ArenaIterMut::new(&mut self.arena.slots, walk)
+    }
+
+/// This function will return an iterator over mutable references to all values in the tree,
+/// ordered by their keys.
+/// # Examples
+/// ```
+/// let mut t : avl_tree::ArenaAVLTree<u64,i32> = (0..5).map(|k| (k, 1)).collect();
+/// for value in t.values_mut() {
+///     *value *= 2;
+/// }
+/// assert_eq!(t.get(&3), Some(&2));
+/// ```
+    pub fn values_mut(&mut self) -> ValuesMut<'_,K,D,P>{
+        self.iter_mut().map(|(_, data)| data)
+    }
+
+/// This function will consume the tree and return an iterator over its keys in order.
+/// # Examples
+/// ```
+/// let t : avl_tree::ArenaAVLTree<u64,i32> = vec![(3,50), (2,25)].into_iter().collect();
+/// assert_eq!(t.into_keys().collect::<Vec<u64>>(), vec![2,3]);
+/// ```
+    pub fn into_keys(self) -> IntoKeys<K,D,P>{
+        self.into_iter().map(|(key, _)| key)
+    }
+
+/// This function will consume the tree and return an iterator over its values, ordered by their
+/// keys.
+/// # Examples
+/// ```
+/// let t : avl_tree::ArenaAVLTree<u64,i32> = vec![(3,50), (2,25)].into_iter().collect();
+/// assert_eq!(t.into_values().collect::<Vec<i32>>(), vec![25,50]);
+/// ```
+    pub fn into_values(self) -> IntoValues<K,D,P>{
+        self.into_iter().map(|(_, data)| data)
+    }
+
+/// This function will remove all key/value pairs from the tree and return them ordered by key. The
+/// pairs are moved out of the arena while iterating, the arena keeps its capacity. Pairs that are
+/// not consumed are dropped together with the iterator.
+/// # Examples
+/// ```
+/// let mut t : avl_tree::ArenaAVLTree<u64,i32> = (0..5).map(|k| (k, 0)).collect();
+/// assert_eq!(t.drain().map(|(k,_)| k).collect::<Vec<u64>>(), vec![0,1,2,3,4]);
+/// assert!(t.empty());
+/// assert!(t.capacity() >= 5);
+/// ```
+    pub fn drain(&mut self) -> ArenaDrain<'_,K,D,P>{
+        let walk = self.walk::<K>(Bound::Unbounded, Bound::Unbounded);
+        let remaining = self.len;
+        self.root = NIL;
+        self.arena.free = NIL;
+        self.len = 0;
+        ArenaDrain{slots: &mut self.arena.slots, walk, remaining}
+    }
+
+/// This function will remove all key/value pairs from the tree. The capacity of the arena is kept.
+/// # Examples
+/// ```
+/// let mut t : avl_tree::ArenaAVLTree<u64,i32> = (0..5).map(|k| (k, 0)).collect();
+/// t.clear();
+/// assert!(t.empty());
+/// assert!(t.capacity() >= 5);
+/// ```
+    pub fn clear(&mut self) {
+        self.arena.slots.clear();
+        self.arena.free = NIL;
+        self.root = NIL;
+        self.len = 0;
+    }
+
+/// This function will keep only the pairs for which `f` returns true and remove all others. `f` is
+/// called once for every pair in ascending order of the keys and may modify the values. The kept
+/// nodes stay in their slots and are relinked into a balanced tree in linear time, the slots of the
+/// removed ones become vacant, so the arena keeps its capacity. If `f` panics, the tree is unchanged.
+/// # Examples
+/// ```
+/// let mut t : avl_tree::ArenaAVLTree<u64,i32> = (0..10).map(|k| (k, 0)).collect();
+/// t.retain(|k, _| k % 2 == 0);
+/// assert_eq!(t.iter().map(|(k,_)| *k).collect::<Vec<u64>>(), vec![0,2,4,6,8]);
+/// assert!(t.capacity() >= 10);
+/// ```
+    pub fn retain<F: FnMut(&K, &mut D) -> bool>(&mut self, mut f: F) {
+        let order : Vec<u32> = self.indices::<K>(Bound::Unbounded, Bound::Unbounded).collect();
+        let mut kept = Vec::with_capacity(order.len());
+        let mut removed = Vec::new();
+        for index in order {
+            let node = self.arena.node_mut(index);
+            if f(&node.key, &mut node.data) { kept.push(index) } else { removed.push(index) }
+        }
+        self.len = kept.len();
+        self.root = self.link_sorted(&kept).0.unwrap_or(NIL);
+        for index in removed {
+            self.arena.release(index);
+        }
+    }
+
+/// This function will return an iterator that removes the key,value pairs within the given range
+/// for which `pred` returns true and yields them in ascending order of the keys. `pred` is called
+/// once for every pair in the range the iterator gets to and may modify the values of the pairs that
+/// are kept. The range is split off in O(log n) and the nodes are visited lazily, the slots of the
+/// extracted ones become vacant right away. When the iterator is dropped, the kept nodes are
+/// relinked in their slots and joined back in, which takes time linear in the size of the range.
+/// Only the pairs the iterator returned are removed, also if `pred` panics.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::ArenaAVLTree<u64,i32> = (0..10).map(|k| (k, k as i32)).collect();
+/// let odd : Vec<(u64,i32)> = t.extract_if(3..8, |k, _| k % 2 == 1).collect();
+/// assert_eq!(odd, vec![(3,3), (5,5), (7,7)]);
+/// assert_eq!(t.len(), 7);
+/// assert!(t.contains(&1));
+/// assert!(!t.contains(&5));
+///
+/// assert_eq!(t.extract_if(.., |_, _| true).next(), Some((0,0)));
+/// assert_eq!(t.len(), 6);
+/// ```
+    pub fn extract_if<'a, Q:Ord+?Sized, R:RangeBounds<Q>, F:FnMut(&K, &mut D) -> bool>(&'a mut self, range: R, pred: F) -> ArenaExtractIf<'a,K,D,F,P> where K: Borrow<Q> {
+        let (before, range, after) = self.split_range(range.start_bound(), range.end_bound());
+        let mut rest = Vec::new();
+        self.push_spine(&mut rest, range.0.unwrap_or(NIL), -1);
+        ArenaExtractIf{tree: self, before, after, kept: Vec::new(), rest, pred}
+    }
+
+/// This function will split the tree at `key`: the tree keeps all key,value pairs with keys smaller
+/// than `key` and all others, including the one stored under `key`, are returned as a new tree.
+/// The tree is cut along the search path of `key` in O(log n), then the returned pairs are moved
+/// into the arena of the new tree. Unlike `AVLTree::split_off` this takes time linear in the number
+/// of returned pairs.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::ArenaAVLTree<u64,i32> = (0..10).map(|k| (k, k as i32)).collect();
+/// let upper = t.split_off(&6);
+/// assert_eq!(t.len(), 6);
+/// assert_eq!(t.last_key_value(), Some((&5,&5)));
+/// assert_eq!(upper.len(), 4);
+/// assert_eq!(upper.first_key_value(), Some((&6,&6)));
+/// ```
+    pub fn split_off<Q:Ord+?Sized>(&mut self, key: &Q) -> ArenaAVLTree<K,D,P> where K: Borrow<Q> {
+        let (below, upper, _) = self.split_range(Bound::Included(key), Bound::Unbounded);
+        self.root = below.0.unwrap_or(NIL);
+        ArenaAVLTree::from_sorted_iter_unchecked(self.move_out(upper.0))
+    }
+
+/// This function will split the tree at `key` into a tree of all smaller keys, the key,value pair
+/// stored under `key` if there is one, and a tree of all bigger keys. The tree of the smaller keys
+/// keeps the arena, the bigger keys are moved into a new one. Unlike `AVLTree::split` this takes time
+/// linear in the number of bigger keys.
+/// # Examples
+/// ```
+/// let t: avl_tree::ArenaAVLTree<u64,i32> = (0..10).map(|k| (k, k as i32)).collect();
+/// let (smaller, pair, bigger) = t.split(&6);
+/// assert_eq!(smaller.len(), 6);
+/// assert_eq!(pair, Some((6,6)));
+/// assert_eq!(bigger.len(), 3);
+/// ```
+    #[allow(clippy::type_complexity)]
+    pub fn split<Q:Ord+?Sized>(mut self, key: &Q) -> (ArenaAVLTree<K,D,P>, Option<(K,D)>, ArenaAVLTree<K,D,P>) where K: Borrow<Q> {
+        let (root, rank) = self.take_root();
+        let (smaller, _, found, bigger, _) = node::split_at_key::<P,_,_,_,_>(&mut self.arena, key, root, rank);
+        self.root = smaller.unwrap_or(NIL);
+        let pair = found.map(|index| {
+            self.len -= 1;
+            self.arena.release(index)
+        });
+        let bigger = ArenaAVLTree::from_sorted_iter_unchecked(self.move_out(bigger));
+        (self, pair, bigger)
+    }
+
+/// This function will remove all key,value pairs with keys within the given bounds from the tree and
+/// return them as a new tree. The range is cut out with two splits and the remaining parts are
+/// joined again in O(log n), then the removed pairs are moved into the arena of the new tree. Unlike
+/// `AVLTree::delete_range` this takes time linear in the number of removed pairs.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t: avl_tree::ArenaAVLTree<u64,i32> = (0..10).map(|k| (k, k as i32)).collect();
+/// let removed = t.delete_range(Bound::Included(&3), Bound::Excluded(&7));
+/// assert_eq!(removed.iter().map(|(k,_)| *k).collect::<Vec<u64>>(), vec![3,4,5,6]);
+/// assert_eq!(t.iter().map(|(k,_)| *k).collect::<Vec<u64>>(), vec![0,1,2,7,8,9]);
+/// ```
+    pub fn delete_range<Q:Ord+?Sized>(&mut self, lower: Bound<&Q>, upper: Bound<&Q>) -> ArenaAVLTree<K,D,P> where K: Borrow<Q> {
+        let (before, range, after) = self.split_range(lower, upper);
+        self.root = self.concat(before, after).0.unwrap_or(NIL);
+        ArenaAVLTree::from_sorted_iter_unchecked(self.move_out(range.0))
+    }
+
+/// This function will move all key,value pairs from `other` into the tree, leaving `other` empty with
+/// its capacity. If a key is part of both trees, the data from `other` replaces the data in this
+/// tree. If all keys of one tree are smaller than all keys of the other tree, the pairs of `other`
+/// are built into a subtree of this arena and joined, which takes time linear in the size of `other`
+/// where `AVLTree::append` takes O(log n). Otherwise the pairs of both trees are merged and rebuilt
+/// into a balanced tree in linear time.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::ArenaAVLTree<u64,i32> = (0..5).map(|k| (k, 0)).collect();
+/// let mut other: avl_tree::ArenaAVLTree<u64,i32> = (5..10).map(|k| (k, 1)).collect();
+/// t.append(&mut other);
+/// assert_eq!(t.len(), 10);
+/// assert!(other.empty());
+///
+/// let mut overlapping: avl_tree::ArenaAVLTree<u64,i32> = (8..12).map(|k| (k, 2)).collect();
+/// t.append(&mut overlapping);
+/// assert_eq!(t.len(), 12);
+/// assert_eq!(t.get(&9), Some(&2));
+/// ```
+    pub fn append(&mut self, other: &mut ArenaAVLTree<K,D,P>) {
+        let before = match (self.last_key_value(), other.first_key_value()) {
+            (Some((max, _)), Some((other_min, _))) => max < other_min,
+            _ => true
+        };
+        let after = match (self.first_key_value(), other.last_key_value()) {
+            (Some((min, _)), Some((other_max, _))) => other_max < min,
+            _ => true
+        };
+        if before || after {
+            let mine = self.take_root();
+            let theirs = self.adopt(other);
+            let (left, right) = if before { (mine, theirs) } else { (theirs, mine) };
+            self.root = self.concat(left, right).0.unwrap_or(NIL);
+        } else {
+            let mine : Vec<(K,D)> = self.drain().collect();
+            let theirs : Vec<(K,D)> = other.drain().collect();
+            let merged = merge_sorted(mine.into_iter(), theirs.into_iter());
+            self.len = merged.len();
+            self.root = self.build_sorted(merged).0.unwrap_or(NIL);
+        }
+    }
+
+/// This function will insert all key,value pairs of `batch` into the tree, as if they were inserted
+/// one after another: if a key appears more than once, the data that comes last wins. The batch is
+/// sorted, built into a subtree of the arena and united with this tree in one pass, which takes
+/// O(m log(n/m + 1)) plus the sorting for a batch of size m, instead of m independent insertions.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::ArenaAVLTree<u64,i32> = (0..5).map(|k| (k, 0)).collect();
+/// t.insert_batch(vec![(9,1), (3,1), (9,2)]);
+/// assert_eq!(t.len(), 6);
+/// assert_eq!(t.get(&3), Some(&1));
+/// assert_eq!(t.get(&9), Some(&2));
+/// ```
+    pub fn insert_batch(&mut self, batch: Vec<(K,D)>) {
+        self.insert_batch_with(batch, |_, _, later| later)
+    }
+
+/// This function will insert all key,value pairs of `batch` into the tree like `insert_batch`, but if
+/// a key is part of the tree or appears more than once in the batch, its data is combined by `f`.
+/// `f` gets the key, the earlier data and the later data, where data in the tree comes before data
+/// in the batch and the batch is read from front to back.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::ArenaAVLTree<u64,i32> = (0..5).map(|k| (k, 0)).collect();
+/// t.insert_batch_with(vec![(9,1), (3,1), (9,2)], |_, earlier, later| earlier + later);
+/// assert_eq!(t.get(&3), Some(&1));
+/// assert_eq!(t.get(&9), Some(&3));
+///
+/// t.insert_batch_with(vec![(3,5), (10,5)], |_, earlier, _| earlier);
+/// assert_eq!(t.get(&3), Some(&1));
+/// assert_eq!(t.get(&10), Some(&5));
+/// ```
+    pub fn insert_batch_with<F: FnMut(&K, D, D) -> D>(&mut self, mut batch: Vec<(K,D)>, mut f: F) {
+        batch.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut sorted : Vec<(K,D)> = Vec::with_capacity(batch.len());
+        for (key, data) in batch {
+            if sorted.last().is_some_and(|last| last.0 == key) {
+                let (last_key, last_data) = sorted.pop().expect("checked above");
+                let data = f(&last_key, last_data, data);
+                sorted.push((last_key, data));
+            } else {
+                sorted.push((key, data));
+            }
+        }
+        let len = sorted.len();
+        let mine = self.take_root();
+        let batch = self.build_sorted(sorted);
+        let mut common = 0;
+        let (root, _) = node::union_with_ranks::<P,_,_,_,_>(&mut self.arena, mine.0, mine.1, batch.0, batch.1, &mut |key, earlier, later| {
+            common += 1;
+            f(key, earlier, later)
+        });
+        self.root = root.unwrap_or(NIL);
+        self.len += len - common;
+    }
+
+/// This function will add all key,value pairs of `other` to the tree. If a key is part of both trees,
+/// its data is combined by `f`, which gets the key, the data from this tree and the data from `other`.
+/// The pairs of `other` are moved into this arena in O(m), then both trees are split and joined
+/// recursively, which takes O(m log(n/m + 1)) for a tree of size m.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::ArenaAVLTree<u64,i32> = (0..5).map(|k| (k, 1)).collect();
+/// let other: avl_tree::ArenaAVLTree<u64,i32> = (3..8).map(|k| (k, 10)).collect();
+/// t.union_with(other, |_, a, b| a + b);
+/// assert_eq!(t.len(), 8);
+/// assert_eq!(t.get(&2), Some(&1));
+/// assert_eq!(t.get(&3), Some(&11));
+/// assert_eq!(t.get(&7), Some(&10));
+/// ```
+    pub fn union_with<F: FnMut(&K, D, D) -> D>(&mut self, mut other: ArenaAVLTree<K,D,P>, mut f: F) {
+        let mine = self.take_root();
+        let theirs = self.adopt(&mut other);
+        let mut common = 0;
+        let (root, _) = node::union_with_ranks::<P,_,_,_,_>(&mut self.arena, mine.0, mine.1, theirs.0, theirs.1, &mut |key, data, other_data| {
+            common += 1;
+            f(key, data, other_data)
+        });
+        self.root = root.unwrap_or(NIL);
+        self.len -= common;
+    }
+
+/// This function will keep only the keys that are part of both trees, combining their data by `f`,
+/// which gets the key, the data from this tree and the data from `other`. The pairs of `other` are
+/// moved into this arena in O(m), the intersection then takes O(m log(n/m + 1)).
+/// # Examples
+/// ```
+/// let mut t: avl_tree::ArenaAVLTree<u64,i32> = (0..5).map(|k| (k, 1)).collect();
+/// let other: avl_tree::ArenaAVLTree<u64,i32> = (3..8).map(|k| (k, 10)).collect();
+/// t.intersection_with(other, |_, a, b| a + b);
+/// assert_eq!(t.len(), 2);
+/// assert_eq!(t.get(&3), Some(&11));
+/// assert_eq!(t.get(&4), Some(&11));
+/// ```
+    pub fn intersection_with<F: FnMut(&K, D, D) -> D>(&mut self, mut other: ArenaAVLTree<K,D,P>, mut f: F) {
+        let mine = self.take_root();
+        let theirs = self.adopt(&mut other);
+        let mut common = 0;
+        let (root, _) = node::intersection_with_ranks::<P,_,_,_,_>(&mut self.arena, mine.0, mine.1, theirs.0, theirs.1, &mut |key, data, other_data| {
+            common += 1;
+            f(key, data, other_data)
+        });
+        self.root = root.unwrap_or(NIL);
+        self.len = common;
+    }
+
+/// This function will remove all keys from the tree that are part of `other`, whose data is
+/// ignored. `other` is read in place, this takes O(m log(n/m + 1)) for trees of sizes m <= n.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::ArenaAVLTree<u64,i32> = (0..5).map(|k| (k, 1)).collect();
+/// let other: avl_tree::ArenaAVLTree<u64,String> = (3..8).map(|k| (k, k.to_string())).collect();
+/// t.difference(&other);
+/// assert_eq!(t.len(), 3);
+/// assert_eq!(t.last_key_value(), Some((&2,&1)));
+/// ```
+    pub fn difference<E>(&mut self, other: &ArenaAVLTree<K,E,P>) {
+        let (mine, rank) = self.take_root();
+        let mut removed = 0;
+        let (root, _) = node::difference_with_rank::<P,_,_,_,_,_>(&mut self.arena, mine, rank, &other.arena, other.root_node(), &mut removed);
+        self.root = root.unwrap_or(NIL);
+        self.len -= removed;
+    }
+
+/// This function will keep the keys that are part of exactly one of both trees, together with their
+/// data. The pairs of `other` are moved into this arena in O(m), the symmetric difference then takes
+/// O(m log(n/m + 1)).
+/// # Examples
+/// ```
+/// let mut t: avl_tree::ArenaAVLTree<u64,i32> = (0..5).map(|k| (k, 1)).collect();
+/// let other: avl_tree::ArenaAVLTree<u64,i32> = (3..8).map(|k| (k, 10)).collect();
+/// t.symmetric_difference(other);
+/// assert_eq!(t.iter().map(|(k,_)| *k).collect::<Vec<u64>>(), vec![0,1,2,5,6,7]);
+/// ```
+    pub fn symmetric_difference(&mut self, mut other: ArenaAVLTree<K,D,P>) {
+        let mine = self.take_root();
+        let theirs = self.adopt(&mut other);
+        let mut common = 0;
+        let (root, _) = node::symmetric_difference_with_ranks::<P,_,_,_>(&mut self.arena, mine.0, mine.1, theirs.0, theirs.1, &mut common);
+        self.root = root.unwrap_or(NIL);
+        self.len -= 2 * common;
+    }
+}
+
+impl <K:Ord,D,P:BalancePolicy> ArenaAVLTree<K,D,P>{
+
+    pub(crate) fn pair(&self, index: u32) -> (&K, &D) {
+        let node = self.arena.node(index);
+        (&node.key, &node.data)
+    }
+
+    pub(crate) fn data_mut(&mut self, index: u32) -> &mut D {
+        &mut self.arena.node_mut(index).data
+    }
+
+    fn pair_if_any(&self, index: u32) -> Option<(&K, &D)> {
+        if index == NIL { None } else { Some(self.pair(index)) }
+    }
+
+    //returns the root as a node of the arena, or None if the tree is empty
+    fn root_node(&self) -> Option<&u32> {
+        if self.root == NIL { None } else { Some(&self.root) }
+    }
+
+    //takes the whole tree out of the root for an operation that splits and joins it
+    fn take_root(&mut self) -> Subtree {
+        let root = subtree(mem::replace(&mut self.root, NIL));
+        (root, node::stored_rank::<P,_,_,_>(&self.arena, root.as_ref()))
+    }
+
+    //returns the index of the node with the given key, or NIL
+    fn find<Q:Ord+?Sized>(&self, key: &Q) -> u32 where K: Borrow<Q> {
+        self.search_path(key).1.unwrap_or(NIL)
+    }
+
+    //searches key and returns its path, together with the index of its node if it was found
+    pub(crate) fn search_path<Q:Ord+?Sized>(&self, key: &Q) -> (SearchPath, Option<u32>) where K: Borrow<Q> {
+        let (search, found) = node::locate::<P,_,_,_,_>(&self.arena, key, self.root_node());
+        (search, found.cloned())
+    }
+
+    //returns the path along the spine on `side` (-1 for left, 1 for right) to the minimal or maximal
+    //node, together with its index, or the path to the root and None if the tree is empty
+    pub(crate) fn spine_path(&self, side: i8) -> (SearchPath, Option<u32>) {
+        let (search, end) = node::spine_path_to_end::<P,_,_,_>(&self.arena, side, self.root_node());
+        (search, end.cloned())
+    }
+
+    //inserts a new node at the end of a path that ends in an empty successor and returns its index,
+    //see arena_entry::ArenaVacantEntry
+    pub(crate) fn insert_at(&mut self, key: K, data: D, search: SearchPath) -> u32 {
+        let leaf = self.arena.create(key, data);
+        let root = subtree(mem::replace(&mut self.root, NIL));
+        self.root = node::insert_along::<P,_,_,_>(&mut self.arena, root, search, 0, leaf).0;
+        self.len += 1;
+        leaf
+    }
+
+    //removes the node at the end of a path and returns its pair, see arena_entry::ArenaOccupiedEntry
+    pub(crate) fn remove_at(&mut self, search: SearchPath) -> (K,D) {
+        let root = mem::replace(&mut self.root, NIL);
+        let (root, _, pair) = node::remove_at_from::<P,_,_,_>(&mut self.arena, root, search, 0);
+        self.root = root.unwrap_or(NIL);
+        self.len -= 1;
+        pair
+    }
+
+    //removes the minimal (side -1) or maximal (side 1) node and returns its pair, or None if the
+    //tree is empty
+    fn remove_end(&mut self, side: i8) -> Option<(K,D)> {
+        let root = subtree(mem::replace(&mut self.root, NIL))?;
+        let (root, _, pair) = node::remove_end_from::<P,_,_,_>(&mut self.arena, root, side);
+        self.root = root.unwrap_or(NIL);
+        self.len -= 1;
+        Some(pair)
+    }
+
+    //returns the successor of the node at index on `side` (-1 for left, 1 for right)
+    pub(crate) fn successor(&self, index: u32, side: i8) -> u32 {
+        self.arena.successor(index, side)
+    }
+
+    //returns the index of the minimal (side -1) or maximal (side 1) node of the subtree at index, or
+    //NIL if it is empty
+    pub(crate) fn spine_end(&self, index: u32, side: i8) -> u32 {
+        let mut end = index;
+        while end != NIL {
+            let next = self.successor(end, side);
+            if next == NIL { break }
+            end = next;
+        }
+        end
+    }
+
+    //pushes the indices of the nodes on the spine of the subtree at index on the given side, the
+    //last one is the node at the end of the spine
+    fn push_spine(&self, stack: &mut Vec<u32>, mut index: u32, side: i8) {
+        while index != NIL {
+            stack.push(index);
+            index = self.successor(index, side);
+        }
+    }
+
+    //returns the index of the node with the smallest key above the lower bound, or NIL
+    pub(crate) fn first_above<Q:Ord+?Sized>(&self, lower: Bound<&Q>) -> u32 where K: Borrow<Q> {
+        let (mut index, mut found) = (self.root, NIL);
+        while index != NIL {
+            let node = self.arena.node(index);
+            if is_above_lower_bound(&node.key, lower) {
+                found = index;
+                index = node.left;
+            } else {
+                index = node.right;
+            }
+        }
+        found
+    }
+
+    //returns the index of the node with the biggest key below the upper bound, or NIL
+    pub(crate) fn last_below<Q:Ord+?Sized>(&self, upper: Bound<&Q>) -> u32 where K: Borrow<Q> {
+        let (mut index, mut found) = (self.root, NIL);
+        while index != NIL {
+            let node = self.arena.node(index);
+            if is_below_upper_bound(&node.key, upper) {
+                found = index;
+                index = node.right;
+            } else {
+                index = node.left;
+            }
+        }
+        found
+    }
+
+    //starts a walk over the nodes within the bounds from both ends
+    fn walk<Q:Ord+?Sized>(&self, lower: Bound<&Q>, upper: Bound<&Q>) -> Walk where K: Borrow<Q> {
+        let mut walk = Walk::default();
+        let mut index = self.root;
+        while index != NIL {
+            let node = self.arena.node(index);
+            if is_above_lower_bound(&node.key, lower) {
+                if is_below_upper_bound(&node.key, upper) { walk.front.push(index) }
+                index = node.left;
+            } else {
+                index = node.right;
+            }
+        }
+        let mut index = self.root;
+        while index != NIL {
+            let node = self.arena.node(index);
+            if is_below_upper_bound(&node.key, upper) {
+                if is_above_lower_bound(&node.key, lower) { walk.back.push(index) }
+                index = node.right;
+            } else {
+                index = node.left;
+            }
+        }
+        match (walk.front.last(), walk.back.last()) {
+            (Some(first), Some(last)) if self.arena.node(*first).key <= self.arena.node(*last).key => walk,
+            _ => Walk::default()
+        }
+    }
+
+    fn indices<'a, Q:Ord+?Sized>(&'a self, lower: Bound<&Q>, upper: Bound<&Q>) -> Indices<'a,K,D,P> where K: Borrow<Q> {
+        Indices{slots: &self.arena.slots, walk: self.walk(lower, upper)}
+    }
+
+    //stores the sorted pairs in new nodes in the order of their keys and links them into a balanced
+    //subtree, which is not counted in len
+    fn build_sorted<I:IntoIterator<Item=(K,D)>>(&mut self, pairs: I) -> Subtree {
+        let arena = &mut self.arena;
+        let indices : Vec<u32> = pairs.into_iter().map(|(key, data)| arena.create(key, data)).collect();
+        node::link_sorted::<P,_,_,_,_>(arena, &mut indices.iter().cloned(), indices.len())
+    }
+
+    //links the nodes at the given indices, which are sorted by their keys, into a balanced subtree
+    //without moving them
+    fn link_sorted(&mut self, indices: &[u32]) -> Subtree {
+        for &index in indices {
+            let node = self.arena.node_mut(index);
+            node.left = NIL;
+            node.right = NIL;
+        }
+        node::link_sorted::<P,_,_,_,_>(&mut self.arena, &mut indices.iter().cloned(), indices.len())
+    }
+
+    //joins two subtrees, all keys of left are smaller than those of right
+    fn concat(&mut self, left: Subtree, right: Subtree) -> Subtree {
+        node::concat_optional_with_ranks::<P,_,_,_>(&mut self.arena, left.0, left.1, right.0, right.1)
+    }
+
+    //takes the root and splits it into the subtrees of the keys below, within and above the bounds
+    fn split_range<Q:Ord+?Sized>(&mut self, lower: Bound<&Q>, upper: Bound<&Q>) -> (Subtree, Subtree, Subtree) where K: Borrow<Q> {
+        let (root, rank) = self.take_root();
+        let (before, before_rank, rest, rest_rank) = node::split_at_bound_with_rank::<P,_,_,_,_>(&mut self.arena, lower, root, rank);
+        let beyond = match upper {
+            Bound::Included(key) => Bound::Excluded(key),
+            Bound::Excluded(key) => Bound::Included(key),
+            Bound::Unbounded => return ((before, before_rank), (rest, rest_rank), (None, 0))
+        };
+        let (range, range_rank, after, after_rank) = node::split_at_bound_with_rank::<P,_,_,_,_>(&mut self.arena, beyond, rest, rest_rank);
+        ((before, before_rank), (range, range_rank), (after, after_rank))
+    }
+
+    //releases all nodes of the subtree, which were counted in len, and returns their pairs in the
+    //order of the keys
+    fn move_out(&mut self, tree: Option<u32>) -> Vec<(K,D)> {
+        let mut walk = Walk::default();
+        self.push_spine(&mut walk.front, tree.unwrap_or(NIL), -1);
+        let order : Vec<u32> = Indices{slots: &self.arena.slots, walk}.collect();
+        self.len -= order.len();
+        order.into_iter().map(|index| self.arena.release(index)).collect()
+    }
+
+    //moves the pairs of other into this arena as a balanced subtree, which is counted in len, other
+    //keeps its capacity
+    fn adopt(&mut self, other: &mut ArenaAVLTree<K,D,P>) -> Subtree {
+        self.len += other.len;
+        self.build_sorted(other.drain())
+    }
+}
+
+//returns the successor on the given side of the node in the slot at index
+fn successor_in<K,D,P:Rebalance>(slots: &[Slot<K,D,P>], index: u32, side: i8) -> u32 {
+    match slots[index as usize] {
+        Slot::Occupied(ref node) => if side > 0 { node.right } else { node.left },
+        Slot::Vacant(_) => panic!("AVL broken")
+    }
+}
+
+//A walk over the indices of the nodes within a range in the order of their keys. Each end keeps the
+//nodes within the range on the path to its next node that have not been visited yet, like
+//RangePairIter. A step only looks at the successors of the node it returns and of nodes that were
+//not visited yet, so the iterators that move the pairs out of their slots or hand out mutable
+//references walk with it as well, without collecting the indices first.
+#[derive(Default)]
+struct Walk {
+    front: Vec<u32>,
+    back: Vec<u32>,
+}
+
+impl Walk {
+
+    //returns the next index from the front (side 1) or from the back (side -1), successor returns
+    //the successor of a node on the given side
+    fn step<F: Fn(u32, i8) -> u32>(&mut self, side: i8, successor: F) -> Option<u32> {
+        let (this, other) = if side > 0 { (&mut self.front, &mut self.back) } else { (&mut self.back, &mut self.front) };
+        let current = this.pop()?;
+        //front and back meet once they point to the same node, after returning it the range is exhausted
+        if other.last() == Some(&current) {
+            this.clear();
+            other.clear();
+            return Some(current)
+        }
+        //the nodes on the other stack were not visited yet and lie beyond current, so current can
+        //only be the first one. Then the other end went down on this side of current and may have
+        //visited some of its successors, the walk goes on at the next node on the path of the other end.
+        let mut index = if other.first() == Some(&current) {
+            other.remove(0);
+            other[0]
+        } else {
+            successor(current, side)
+        };
+        while index != NIL {
+            this.push(index);
+            index = successor(index, -side);
+        }
+        Some(current)
+    }
+}
+
+//Iterator over the indices of the nodes within a range, ordered by their keys
+struct Indices<'a, K:'a, D:'a, P:'a+Rebalance> {
+    slots: &'a [Slot<K,D,P>],
+    walk: Walk,
+}
+
+impl<'a, K:'a, D:'a, P:Rebalance> Iterator for Indices<'a, K, D, P> {
+
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        let slots = self.slots;
+        self.walk.step(1, |index, side| successor_in(slots, index, side))
+    }
+}
+
+impl<'a, K:'a, D:'a, P:Rebalance> DoubleEndedIterator for Indices<'a, K, D, P> {
+
+    fn next_back(&mut self) -> Option<u32> {
+        let slots = self.slots;
+        self.walk.step(-1, |index, side| successor_in(slots, index, side))
+    }
+}
+
+/// Iterator over (key,value) pairs of an `ArenaAVLTree` within the given bounds. It can be consumed
+/// from both ends.
+pub struct ArenaIter<'a, K:'a+Ord, D:'a, P:'a+BalancePolicy=Avl> {
+    tree: &'a ArenaAVLTree<K,D,P>,
+    indices: Indices<'a, K, D, P>,
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> Iterator for ArenaIter<'a, K, D, P> {
+
+    type Item = (&'a K,&'a D);
+
+    fn next(&mut self) -> Option<(&'a K,&'a D)> {
+        let tree = self.tree;
+        self.indices.next().map(|index| tree.pair(index))
+    }
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> DoubleEndedIterator for ArenaIter<'a, K, D, P> {
+
+    fn next_back(&mut self) -> Option<(&'a K,&'a D)> {
+        let tree = self.tree;
+        self.indices.next_back().map(|index| tree.pair(index))
+    }
+}
+
+/// Iterator over (key,value) pairs of an `ArenaAVLTree` within the given bounds, handing out mutable
+/// references to the values. It can be consumed from both ends.
+pub struct ArenaIterMut<'a, K:'a, D:'a, P:'a+BalancePolicy=Avl> {
+    //the slots of the tree, which is borrowed mutably for 'a. They are only accessed through this
+    //pointer, so that the handed out references to some slots stay valid while the walk reads others.
+    slots: *mut Slot<K,D,P>,
+    capacity: usize,
+    walk: Walk,
+    marker: PhantomData<&'a mut [Slot<K,D,P>]>,
+}
+
+//like a mutable reference to the slots, the balance information of every policy is Send and Sync
+unsafe impl<'a, K:'a+Send, D:'a+Send, P:BalancePolicy> Send for ArenaIterMut<'a, K, D, P> {}
+unsafe impl<'a, K:'a+Sync, D:'a+Sync, P:BalancePolicy> Sync for ArenaIterMut<'a, K, D, P> {}
+
+impl<'a, K:'a, D:'a, P:BalancePolicy> ArenaIterMut<'a, K, D, P> {
+
+    fn new(slots: &'a mut [Slot<K,D,P>], walk: Walk) -> ArenaIterMut<'a,K,D,P> {
+        ArenaIterMut{slots: slots.as_mut_ptr(), capacity: slots.len(), walk, marker: PhantomData}
+    }
+
+    fn step(&mut self, side: i8) -> Option<(&'a K,&'a mut D)> {
+        let (slots, capacity) = (self.slots, self.capacity);
+        let index = self.walk.step(side, |index, side| {
+            assert!((index as usize) < capacity, "AVL broken");
+            //SAFETY: the walk only asks for the successors of nodes it has not returned yet, so no
+            //mutable reference to this slot was handed out
+            match unsafe { &*slots.add(index as usize) } {
+                Slot::Occupied(ref node) => if side > 0 { node.right } else { node.left },
+                Slot::Vacant(_) => panic!("AVL broken")
+            }
+        })?;
+        assert!((index as usize) < capacity, "AVL broken");
+        //SAFETY: every node of the tree has a single parent, so the walk returns every index at most
+        //once and the references handed out point to distinct slots. The tree stays borrowed mutably
+        //for 'a, so nothing else accesses the slots meanwhile.
+        match unsafe { &mut *slots.add(index as usize) } {
+            Slot::Occupied(ref mut node) => Some((&node.key, &mut node.data)),
+            Slot::Vacant(_) => panic!("AVL broken")
+        }
+    }
+}
+
+impl<'a, K:'a, D:'a, P:BalancePolicy> Iterator for ArenaIterMut<'a, K, D, P> {
+
+    type Item = (&'a K,&'a mut D);
+
+    fn next(&mut self) -> Option<(&'a K,&'a mut D)> {
+        self.step(1)
+    }
+}
+
+impl<'a, K:'a, D:'a, P:BalancePolicy> DoubleEndedIterator for ArenaIterMut<'a, K, D, P> {
+
+    fn next_back(&mut self) -> Option<(&'a K,&'a mut D)> {
+        self.step(-1)
+    }
+}
+
+//moves the pair out of the node at index, the slot is left vacant outside of any free list
+fn take_pair<K,D,P:Rebalance>(slots: &mut [Slot<K,D,P>], index: u32) -> (K, D) {
+    match mem::replace(&mut slots[index as usize], Slot::Vacant(NIL)) {
+        Slot::Occupied(node) => (node.key, node.data),
+        Slot::Vacant(_) => panic!("AVL broken")
+    }
+}
+
+//moves the pair of the next node from the front (side 1) or the back (side -1) of the walk out of its
+//slot, after the walk looked at its successors
+fn take_step<K,D,P:Rebalance>(slots: &mut [Slot<K,D,P>], walk: &mut Walk, side: i8) -> Option<(K,D)> {
+    let index = walk.step(side, |index, side| successor_in(slots, index, side))?;
+    Some(take_pair(slots, index))
+}
+
+/// Iterator that consumes an `ArenaAVLTree` and yields its owned (key,value) pairs ordered by key.
+pub struct ArenaIntoIter<K,D,P:BalancePolicy=Avl> {
+    slots: Vec<Slot<K,D,P>>,
+    walk: Walk,
+    remaining: usize,
+}
+
+impl<K,D,P:BalancePolicy> Iterator for ArenaIntoIter<K, D, P> {
+
+    type Item = (K,D);
+
+    fn next(&mut self) -> Option<(K,D)> {
+        let pair = take_step(&mut self.slots, &mut self.walk, 1)?;
+        self.remaining -= 1;
+        Some(pair)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K,D,P:BalancePolicy> DoubleEndedIterator for ArenaIntoIter<K, D, P> {
+
+    fn next_back(&mut self) -> Option<(K,D)> {
+        let pair = take_step(&mut self.slots, &mut self.walk, -1)?;
+        self.remaining -= 1;
+        Some(pair)
+    }
+}
+
+/// Iterator that removes all (key,value) pairs from an `ArenaAVLTree` and yields them ordered by key.
+/// The arena of the tree keeps its capacity, the pairs that were not consumed are dropped with the
+/// iterator.
+pub struct ArenaDrain<'a, K:'a, D:'a, P:'a+BalancePolicy=Avl> {
+    slots: &'a mut Vec<Slot<K,D,P>>,
+    walk: Walk,
+    remaining: usize,
+}
+
+impl<'a, K:'a, D:'a, P:BalancePolicy> Iterator for ArenaDrain<'a, K, D, P> {
+
+    type Item = (K,D);
+
+    fn next(&mut self) -> Option<(K,D)> {
+        let pair = take_step(self.slots, &mut self.walk, 1)?;
+        self.remaining -= 1;
+        Some(pair)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K:'a, D:'a, P:BalancePolicy> DoubleEndedIterator for ArenaDrain<'a, K, D, P> {
+
+    fn next_back(&mut self) -> Option<(K,D)> {
+        let pair = take_step(self.slots, &mut self.walk, -1)?;
+        self.remaining -= 1;
+        Some(pair)
+    }
+}
+
+impl<'a, K:'a, D:'a, P:BalancePolicy> Drop for ArenaDrain<'a, K, D, P> {
+    fn drop(&mut self) {
+        self.slots.clear();
+    }
+}
+
+/// Iterator that removes the (key,value) pairs within a range of an `ArenaAVLTree` for which a
+/// predicate returns true and yields them in ascending order of the keys. It is returned by
+/// `ArenaAVLTree::extract_if`, the kept nodes are joined back into the tree when it is dropped.
+pub struct ArenaExtractIf<'a, K:'a+Ord, D:'a, F, P:'a+BalancePolicy=Avl> {
+    tree: &'a mut ArenaAVLTree<K,D,P>,
+    //the parts of the tree below and above the range
+    before: Subtree,
+    after: Subtree,
+    //the visited nodes of the range that were kept, and the left spines of the subtrees of the range
+    //that were not visited yet, the top of the stack is the next node
+    kept: Vec<u32>,
+    rest: Vec<u32>,
+    pred: F,
+}
+
+impl<'a, K:'a+Ord, D:'a, F:FnMut(&K, &mut D) -> bool, P:BalancePolicy> Iterator for ArenaExtractIf<'a, K, D, F, P> {
+
+    type Item = (K,D);
+
+    fn next(&mut self) -> Option<(K,D)> {
+        while let Some(index) = self.rest.pop() {
+            let right = self.tree.successor(index, 1);
+            self.tree.push_spine(&mut self.rest, right, -1);
+            //the node is kept until pred returns, so a panicking pred does not lose it
+            self.kept.push(index);
+            let node = self.tree.arena.node_mut(index);
+            if (self.pred)(&node.key, &mut node.data) {
+                self.kept.pop();
+                self.tree.len -= 1;
+                return Some(self.tree.arena.release(index))
+            }
+        }
+        None
+    }
+}
+
+impl<'a, K:'a+Ord, D:'a, F, P:BalancePolicy> Drop for ArenaExtractIf<'a, K, D, F, P> {
+    fn drop(&mut self) {
+        while let Some(index) = self.rest.pop() {
+            let right = self.tree.successor(index, 1);
+            self.tree.push_spine(&mut self.rest, right, -1);
+            self.kept.push(index);
+        }
+        let kept = self.tree.link_sorted(&self.kept);
+        let below = self.tree.concat(self.before, kept);
+        self.tree.root = self.tree.concat(below, self.after).0.unwrap_or(NIL);
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy> IntoIterator for ArenaAVLTree<K,D,P> {
+    type Item = (K,D);
+    type IntoIter = ArenaIntoIter<K,D,P>;
+
+/// This function will consume the tree and return an iterator over its owned key/value pairs
+/// ordered by key.
+/// # Examples
+/// ```
+/// let t : avl_tree::ArenaAVLTree<u64,i32> = (0..3).map(|k| (k, 0)).collect();
+/// assert_eq!(t.into_iter().collect::<Vec<(u64,i32)>>(), vec![(0,0), (1,0), (2,0)]);
+/// ```
+    fn into_iter(self) -> ArenaIntoIter<K,D,P> {
+        let walk = self.walk::<K>(Bound::Unbounded, Bound::Unbounded);
+        ArenaIntoIter{slots: self.arena.slots, walk, remaining: self.len}
+    }
+}
+
+impl<'a, K:Ord,D,P:BalancePolicy> IntoIterator for &'a ArenaAVLTree<K,D,P> {
+    type Item = (&'a K,&'a D);
+    type IntoIter = ArenaIter<'a,K,D,P>;
+
+    fn into_iter(self) -> ArenaIter<'a,K,D,P> {
+        self.iter()
+    }
+}
+
+impl<'a, K:Ord,D,P:BalancePolicy> IntoIterator for &'a mut ArenaAVLTree<K,D,P> {
+    type Item = (&'a K,&'a mut D);
+    type IntoIter = ArenaIterMut<'a,K,D,P>;
+
+    fn into_iter(self) -> ArenaIterMut<'a,K,D,P> {
+        self.iter_mut()
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy> Default for ArenaAVLTree<K,D,P> {
+    fn default() -> ArenaAVLTree<K,D,P> {
+        ArenaAVLTree::with_capacity(0)
+    }
+}
+
+impl<K:Ord+fmt::Debug,D:fmt::Debug,P:BalancePolicy> fmt::Debug for ArenaAVLTree<K,D,P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K:Ord,D:PartialEq,P:BalancePolicy> PartialEq for ArenaAVLTree<K,D,P> {
+    fn eq(&self, other: &ArenaAVLTree<K,D,P>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<K:Ord,D:Eq,P:BalancePolicy> Eq for ArenaAVLTree<K,D,P> {}
+
+impl<K:Ord,D:PartialOrd,P:BalancePolicy> PartialOrd for ArenaAVLTree<K,D,P> {
+    fn partial_cmp(&self, other: &ArenaAVLTree<K,D,P>) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<K:Ord,D:Ord,P:BalancePolicy> Ord for ArenaAVLTree<K,D,P> {
+/// This function will compare both trees lexicographically by their pairs in the order of the keys,
+/// like `AVLTree` does.
+/// # Examples
+/// ```
+/// let small : avl_tree::ArenaAVLTree<u64,i32> = (0..3).map(|k| (k, 0)).collect();
+/// let big : avl_tree::ArenaAVLTree<u64,i32> = (0..3).map(|k| (k, 1)).collect();
+/// assert!(small < big);
+/// assert_eq!(small.clone().max(big.clone()), big);
+/// ```
+    fn cmp(&self, other: &ArenaAVLTree<K,D,P>) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+// The hash only depends on the pairs, so it is the same as the hash of an AVLTree with these pairs.
+impl<K:Ord+Hash,D:Hash,P:BalancePolicy> Hash for ArenaAVLTree<K,D,P> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for pair in self.iter() {
+            pair.hash(state);
+        }
+    }
+}
+
+/// This will return the data stored under the given key and panic if the key is not part of the
+/// tree.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// assert_eq!(t[&2], 25);
+/// ```
+impl<K:Ord+Borrow<Q>,D,P:BalancePolicy,Q:Ord+?Sized> Index<&Q> for ArenaAVLTree<K,D,P> {
+    type Output = D;
+
+    fn index(&self, key: &Q) -> &D {
+        self.get(key).expect("key not found in ArenaAVLTree")
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy> FromIterator<(K,D)> for ArenaAVLTree<K,D,P> {
+    fn from_iter<I: IntoIterator<Item=(K,D)>>(pairs: I) -> ArenaAVLTree<K,D,P> {
+        let mut tree = ArenaAVLTree::default();
+        tree.extend(pairs);
+        tree
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy> Extend<(K,D)> for ArenaAVLTree<K,D,P> {
+    fn extend<I: IntoIterator<Item=(K,D)>>(&mut self, pairs: I) {
+        for (key, data) in pairs {
+            self.insert(key, data);
+        }
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy,const N: usize> From<[(K,D); N]> for ArenaAVLTree<K,D,P> {
+/// This function will construct a tree from the given (key,value) pairs. If a key occurs more than
+/// once, the last value wins.
+/// # Examples
+/// ```
+/// let t : avl_tree::ArenaAVLTree<u64,i32> = avl_tree::ArenaAVLTree::from([(3,50),(2,25),(3,60)]);
+/// assert_eq!(t.len(), 2);
+/// assert_eq!(t[&3], 60);
+/// ```
+    fn from(pairs: [(K,D); N]) -> ArenaAVLTree<K,D,P> {
+        IntoIterator::into_iter(pairs).collect()
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy> From<BTreeMap<K,D>> for ArenaAVLTree<K,D,P> {
+    fn from(map: BTreeMap<K,D>) -> ArenaAVLTree<K,D,P> {
+        ArenaAVLTree::from_sorted_iter_unchecked(map)
+    }
+}
+
+
+#[cfg(test)]
+impl<K:Ord,D,P:BalancePolicy> ArenaAVLTree<K,D,P> {
+
+    //checks that the keys are sorted and every node follows the rules of the policy, and that every
+    //slot is either part of the tree or of the free list
+    fn is_consistent(&self) -> bool {
+        let mut vacant = 0;
+        let mut index = self.arena.free;
+        while index != NIL {
+            match self.arena.slots[index as usize] {
+                Slot::Vacant(next) => index = next,
+                Slot::Occupied(_) => return false
+            }
+            vacant += 1;
+        }
+        let keys : Vec<&K> = self.iter().map(|(k,_)| k).collect();
+        P::checked_rank(&self.arena, self.root_node()).is_some() && keys.windows(2).all(|pair| pair[0] < pair[1]) &&
+            keys.len() == self.len && vacant + self.len == self.arena.slots.len()
+    }
+}
+
+#[test]
+fn test_arena_layout(){
+    use balance::Wavl;
+    //the tag of a slot is stored in the unused values of the rank differences
+    assert_eq!(mem::size_of::<Slot<u64,u64,Avl>>(), mem::size_of::<ArenaNode<u64,u64,Avl>>());
+    assert_eq!(mem::size_of::<Slot<u64,u64,Wavl>>(), mem::size_of::<ArenaNode<u64,u64,Wavl>>());
+    assert_eq!(mem::size_of::<ArenaNode<u64,u64,Avl>>(), 32);
+    assert_eq!(mem::size_of::<ArenaNode<u64,u32,Avl>>(), 24);
+}
+
+#[test]
+fn test_arena_lazy_iterators(){
+    //the slots are not in the order of the keys after the removals
+    let mut tree : ArenaAVLTree<u64,u64> = (0..200).rev().map(|k| (k, 0)).collect();
+    for key in (0..200).step_by(3) {
+        tree.delete(&key);
+    }
+    tree.extend((0..200).step_by(6).map(|k| (k, 0)));
+    let expected : Vec<u64> = tree.iter().map(|(k,_)| *k).collect();
+    {
+        //all references are alive at the same time, taken from both ends
+        let mut iter = tree.range_mut(20..180);
+        let mut values = Vec::new();
+        while let Some((key, value)) = iter.next() {
+            values.push((*key, value));
+            if let Some((key, value)) = iter.next_back() {
+                values.push((*key, value));
+            }
+        }
+        assert_eq!(values.len(), expected.iter().filter(|k| (20..180).contains(*k)).count());
+        for (key, value) in values {
+            *value = key;
+        }
+    }
+    assert!(tree.iter().all(|(k, v)| *v == if (20..180).contains(k) { *k } else { 0 }));
+    assert_eq!(tree.iter_mut().rev().map(|(k,_)| *k).collect::<Vec<u64>>(), expected.iter().rev().cloned().collect::<Vec<u64>>());
+
+    let mut drain = tree.clone().into_iter();
+    assert_eq!(drain.size_hint(), (expected.len(), Some(expected.len())));
+    assert_eq!(drain.next_back().map(|(k,_)| k), expected.last().cloned());
+    assert_eq!(drain.map(|(k,_)| k).collect::<Vec<u64>>(), expected[..expected.len() - 1].to_vec());
+    let mut drain = tree.drain();
+    assert_eq!(drain.next().map(|(k,_)| k), Some(0));
+    assert_eq!(drain.next_back().map(|(k,_)| k), Some(199));
+    drop(drain);
+    assert!(tree.is_consistent() && tree.is_empty());
+
+    //both ends in random order, each pair is handed out once
+    for _ in 0..200 {
+        let tree : ArenaAVLTree<u64,u64> = (0..::rand::random::<u64>() % 60).map(|_| (::rand::random::<u64>() % 100, 0)).collect();
+        let (lower, upper) = (::rand::random::<u64>() % 100, ::rand::random::<u64>() % 100);
+        let expected : Vec<u64> = tree.range(lower..upper).map(|(k,_)| *k).collect();
+        let mut mutable = tree.clone();
+        {
+            let (mut front, mut back, mut values) = (Vec::new(), Vec::new(), Vec::new());
+            let mut iter = mutable.range_mut(lower..upper);
+            loop {
+                let forward = ::rand::random::<bool>();
+                match if forward { iter.next() } else { iter.next_back() } {
+                    Some((key, value)) => {
+                        if forward { front.push(*key) } else { back.push(*key) }
+                        values.push(value);
+                    },
+                    None => break
+                }
+            }
+            back.reverse();
+            front.extend(back);
+            assert_eq!(front, expected);
+            for value in values { *value += 1 }
+        }
+        assert!(mutable.iter().all(|(k, v)| *v == (lower..upper).contains(k) as u64));
+
+        let all : Vec<u64> = tree.iter().map(|(k,_)| *k).collect();
+        let (mut front, mut back) = (Vec::new(), Vec::new());
+        let mut owned = tree.into_iter();
+        loop {
+            let forward = ::rand::random::<bool>();
+            match if forward { owned.next() } else { owned.next_back() } {
+                Some((key, _)) => if forward { front.push(key) } else { back.push(key) },
+                None => break
+            }
+        }
+        back.reverse();
+        front.extend(back);
+        assert_eq!(front, all);
+    }
+}
+
+#[cfg(test)]
+fn check_arena_insert_remove<P:BalancePolicy>(mut tree: ArenaAVLTree<u64,u64,P>){
+    let mut model = BTreeMap::new();
+    for round in 0..5000 {
+        let key = ::rand::random::<u16>() as u64 % 300;
+        match ::rand::random::<u8>() % 8 {
+            0 | 1 => {
+                tree.insert(key, round);
+                model.insert(key, round);
+            },
+            5 => {
+                *tree.entry(key).or_insert(0) += 1;
+                *model.entry(key).or_insert(0) += 1;
+            },
+            6 => match tree.entry(key) {
+                ArenaEntry::Occupied(entry) => assert_eq!(Some(entry.remove()), model.remove(&key)),
+                ArenaEntry::Vacant(_) => assert!(!model.contains_key(&key))
+            },
+            7 => {
+                //replaces the pair above key by one with the key in the middle of the gap before it
+                let mut cursor = tree.lower_bound_cursor_mut(Bound::Excluded(&key));
+                let removed = cursor.remove_current();
+                assert_eq!(removed.map(|(k,_)| k), model.range(key + 1..).next().map(|(k,_)| *k));
+                if let Some((removed, _)) = removed {
+                    model.remove(&removed);
+                    let middle = key + (removed - key)/2;
+                    if cursor.insert_before(middle, round).is_ok() { model.insert(middle, round); }
+                    assert_eq!(cursor.key(), model.range(removed..).next().map(|(k,_)| k));
+                }
+            },
+            2 => assert_eq!(tree.remove_entry(&key), model.remove(&key).map(|data| (key, data))),
+            3 => assert_eq!(tree.first_entry().map(|entry| entry.remove_entry()), model.keys().next().cloned().map(|min| (min, model.remove(&min).unwrap()))),
+            _ => assert_eq!(tree.pop_max(), model.keys().next_back().cloned().map(|max| (max, model.remove(&max).unwrap())))
+        }
+        assert!(tree.is_consistent());
+        assert_eq!(tree.len(), model.len());
+        //vacant slots are reused, so the arena never holds more slots than the tree had nodes
+        assert!(tree.arena.slots.len() <= 300);
+    }
+    assert!(tree.iter().eq(model.iter()));
+}
+
+#[test]
+fn test_arena_insert_remove(){
+    use balance::{Wavl, WeightBalanced};
+    check_arena_insert_remove(ArenaAVLTree::new());
+    check_arena_insert_remove(ArenaAVLTree::with_policy(Wavl));
+    check_arena_insert_remove(ArenaAVLTree::with_policy(WeightBalanced));
+}
+
+#[test]
+fn test_arena_shrink_to_fit(){
+    let mut tree : ArenaAVLTree<u64,u64> = (0..1000).map(|k| (k, k)).collect();
+    tree.retain(|k, _| k % 3 == 0);
+    assert!(tree.is_consistent());
+    for key in 0..1000 {
+        if key % 3 == 1 { tree.insert(key, key) }
+        if key % 6 == 0 { tree.delete(&key); }
+    }
+    assert!(tree.is_consistent());
+    let before : Vec<(u64,u64)> = tree.iter().map(|(k,d)| (*k, *d)).collect();
+    tree.shrink_to_fit();
+    assert!(tree.is_consistent());
+    assert_eq!(tree.arena.slots.len(), before.len());
+    assert_eq!(tree.capacity(), before.len());
+    assert!(tree.iter().map(|(k,d)| (*k, *d)).eq(before.iter().cloned()));
+    //after compacting, the slots are in the order of the keys
+    assert!(tree.indices::<u64>(Bound::Unbounded, Bound::Unbounded).eq(0..before.len() as u32));
+    tree.insert(1000, 0);
+    assert!(tree.is_consistent());
+}
+
+#[test]
+fn test_arena_keeps_capacity(){
+    let mut tree : ArenaAVLTree<u64,u64> = ArenaAVLTree::new();
+    for key in 0..1000 {
+        tree.push_back(key, key).unwrap();
+        assert!(tree.is_consistent());
+    }
+    assert!(tree.push_back(999, 0).is_err());
+    let capacity = tree.capacity();
+    tree.retain(|k, _| k % 3 == 0);
+    assert!(tree.is_consistent());
+    assert_eq!(tree.len(), 334);
+    assert_eq!(tree.capacity(), capacity);
+    //the removed nodes left vacant slots behind, which are reused before the arena grows
+    for key in 1000..1666 {
+        tree.push_back(key, key).unwrap();
+    }
+    assert!(tree.is_consistent());
+    assert_eq!(tree.arena.slots.len(), 1000);
+    assert_eq!(tree.drain().take(10).count(), 10);
+    assert!(tree.is_consistent());
+    assert_eq!(tree.capacity(), capacity);
+}
+
+#[cfg(test)]
+fn check_arena_split_join<P:BalancePolicy>(mut tree: ArenaAVLTree<u64,u64,P>){
+    let mut model = BTreeMap::new();
+    for round in 0..500 {
+        let key = ::rand::random::<u16>() as u64 % 1000;
+        let other_pairs : BTreeMap<u64,u64> = (0..::rand::random::<u8>() % 100).map(|_| (::rand::random::<u16>() as u64 % 1000, round)).collect();
+        let mut other : ArenaAVLTree<u64,u64,P> = other_pairs.clone().into();
+        match ::rand::random::<u8>() % 10 {
+            0 => {
+                tree.append(&mut other);
+                assert!(other.is_empty() && other.is_consistent());
+                model.extend(other_pairs);
+            },
+            1 => {
+                tree.union_with(other, |_, a, b| a + b);
+                for (k, v) in other_pairs { *model.entry(k).or_insert(0) += v }
+            },
+            2 => {
+                tree.intersection_with(other, |_, a, b| a ^ b);
+                model = model.into_iter().filter_map(|(k, v)| other_pairs.get(&k).map(|w| (k, v ^ w))).collect();
+            },
+            3 => {
+                tree.difference(&other);
+                model.retain(|k, _| !other_pairs.contains_key(k));
+            },
+            4 => {
+                tree.symmetric_difference(other);
+                for (k, v) in other_pairs {
+                    if model.remove(&k).is_none() { model.insert(k, v); }
+                }
+            },
+            5 => {
+                tree.insert_batch(other_pairs.clone().into_iter().rev().collect());
+                model.extend(other_pairs);
+            },
+            6 => {
+                let mut upper = tree.split_off(&key);
+                let model_upper = model.split_off(&key);
+                assert!(upper.is_consistent());
+                assert!(upper.iter().eq(model_upper.iter()));
+                if round % 2 == 0 {
+                    tree.append(&mut upper);
+                    model.extend(model_upper);
+                }
+            },
+            7 => {
+                let removed = tree.delete_range(Bound::Included(&key), Bound::Excluded(&(key + 100)));
+                let model_removed : Vec<(u64,u64)> = model.range(key..key + 100).map(|(k,v)| (*k, *v)).collect();
+                for (k, _) in &model_removed { model.remove(k); }
+                assert!(removed.is_consistent());
+                assert!(removed.into_iter().eq(model_removed));
+            },
+            8 => {
+                let (smaller, pair, mut bigger) = mem::take(&mut tree).split(&key);
+                assert!(smaller.is_consistent() && bigger.is_consistent());
+                assert_eq!(pair, model.get(&key).map(|v| (key, *v)));
+                assert!(smaller.iter().eq(model.range(..key)));
+                assert!(bigger.iter().eq(model.range(key + 1..)));
+                tree = smaller;
+                tree.append(&mut bigger);
+                if let Some((k, v)) = pair { tree.insert(k, v) }
+            },
+            _ => {
+                let extracted : Vec<(u64,u64)> = tree.extract_if(key..key + 200, |k, v| { *v += 1; k % 3 == 0 }).collect();
+                for (_, v) in model.range_mut(key..key + 200) { *v += 1 }
+                let model_extracted : Vec<(u64,u64)> = model.range(key..key + 200).filter(|(k,_)| *k % 3 == 0).map(|(k,v)| (*k, *v)).collect();
+                for (k, _) in &model_extracted { model.remove(k); }
+                assert_eq!(extracted, model_extracted);
+            }
+        }
+        assert!(tree.is_consistent());
+        assert!(tree.iter().eq(model.iter()));
+    }
+}
+
+#[test]
+fn test_arena_split_join(){
+    use balance::{Wavl, WeightBalanced};
+    check_arena_split_join(ArenaAVLTree::new());
+    check_arena_split_join(ArenaAVLTree::with_policy(Wavl));
+    check_arena_split_join(ArenaAVLTree::with_policy(WeightBalanced));
+}
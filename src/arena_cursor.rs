@@ -0,0 +1,369 @@
+use ::arena::{ArenaAVLTree, NIL};
+use ::balance::{Avl, BalancePolicy};
+use ::node::{is_above_lower_bound, is_below_upper_bound};
+use error::CursorInsertError;
+use std::borrow::Borrow;
+use std::ops::Bound;
+
+/// A cursor over an `ArenaAVLTree` that points either at one of its pairs or at the ghost position,
+/// which lies behind the maximal and in front of the minimal pair. It keeps the indices of the nodes
+/// on the path from the root to its pair, so moving to a neighbouring pair is amortized O(1). It is
+/// returned by `ArenaAVLTree::lower_bound_cursor` and `ArenaAVLTree::upper_bound_cursor`.
+pub struct ArenaCursor<'a, K:'a+Ord, D:'a, P:'a+BalancePolicy=Avl> {
+    tree: &'a ArenaAVLTree<K,D,P>,
+    path: Vec<u32>,
+}
+
+/// returns a cursor pointing at the minimal pair of `tree` above the bound `from`
+pub fn lower_bound_cursor<'a, K:'a+Ord+Borrow<Q>, D:'a, P:BalancePolicy, Q:Ord+?Sized>(tree: &'a ArenaAVLTree<K,D,P>, from: Bound<&Q>) -> ArenaCursor<'a, K, D, P>{
+    let mut path = Vec::new();
+    let mut target_len = 0;
+    let mut index = tree.root;
+    while index != NIL {
+        path.push(index);
+        if is_above_lower_bound(tree.pair(index).0, from) {
+            target_len = path.len();
+            index = tree.successor(index, -1);
+        } else {
+            index = tree.successor(index, 1);
+        }
+    }
+    path.truncate(target_len);
+    ArenaCursor{tree, path}
+}
+
+/// returns a cursor pointing at the maximal pair of `tree` below the bound `to`
+pub fn upper_bound_cursor<'a, K:'a+Ord+Borrow<Q>, D:'a, P:BalancePolicy, Q:Ord+?Sized>(tree: &'a ArenaAVLTree<K,D,P>, to: Bound<&Q>) -> ArenaCursor<'a, K, D, P>{
+    let mut path = Vec::new();
+    let mut target_len = 0;
+    let mut index = tree.root;
+    while index != NIL {
+        path.push(index);
+        if is_below_upper_bound(tree.pair(index).0, to) {
+            target_len = path.len();
+            index = tree.successor(index, 1);
+        } else {
+            index = tree.successor(index, -1);
+        }
+    }
+    path.truncate(target_len);
+    ArenaCursor{tree, path}
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> ArenaCursor<'a, K, D, P> {
+
+    //pushes the path from the node at index along its spine on `side` to its minimal or maximal node
+    fn push_spine(&mut self, mut index: u32, side: i8) {
+        while index != NIL {
+            self.path.push(index);
+            index = self.tree.successor(index, side);
+        }
+    }
+
+    //returns the successor on `side` of the current node, or the root at the ghost position
+    fn successor(&self, side: i8) -> u32 {
+        match self.path.last() {
+            Some(&index) => self.tree.successor(index, side),
+            None => self.tree.root
+        }
+    }
+
+    //returns the deepest node on the path whose successor on `side` is on the path as well
+    fn deepest_parent(&self, side: i8) -> Option<u32> {
+        self.path.windows(2).rev()
+            .find(|pair| self.tree.successor(pair[0], side) == pair[1])
+            .map(|pair| pair[0])
+    }
+
+/// This function will return the key the cursor points at, or None at the ghost position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::ArenaAVLTree<u64,i32> = (0..10).map(|k| (k*2, 0)).collect();
+/// assert_eq!(t.lower_bound_cursor(Bound::Included(&5)).key(), Some(&6));
+/// assert_eq!(t.lower_bound_cursor(Bound::Excluded(&18)).key(), None);
+/// ```
+    pub fn key(&self) -> Option<&'a K> {
+        let tree = self.tree;
+        self.path.last().map(|&index| tree.pair(index).0)
+    }
+
+/// This function will return the value the cursor points at, or None at the ghost position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// assert_eq!(t.upper_bound_cursor(Bound::Included(&2)).value(), Some(&25));
+/// assert_eq!(t.upper_bound_cursor(Bound::Excluded(&2)).value(), None);
+/// ```
+    pub fn value(&self) -> Option<&'a D> {
+        let tree = self.tree;
+        self.path.last().map(|&index| tree.pair(index).1)
+    }
+
+/// This function will move the cursor to the next pair. From the maximal pair it moves to the ghost
+/// position, and from the ghost position to the minimal pair.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::ArenaAVLTree<u64,i32> = (0..3).map(|k| (k, 0)).collect();
+/// let mut cursor = t.lower_bound_cursor(Bound::Included(&1));
+/// cursor.move_next();
+/// assert_eq!(cursor.key(), Some(&2));
+/// cursor.move_next();
+/// assert_eq!(cursor.key(), None);
+/// cursor.move_next();
+/// assert_eq!(cursor.key(), Some(&0));
+/// ```
+    pub fn move_next(&mut self) {
+        match self.successor(1) {
+            //go up until we leave a left successor, its parent is the next node
+            NIL => while let Some(child) = self.path.pop() {
+                if self.path.last().is_some_and(|&parent| self.tree.successor(parent, -1) == child) { return }
+            },
+            right => self.push_spine(right, -1)
+        }
+    }
+
+/// This function will move the cursor to the previous pair. From the minimal pair it moves to the
+/// ghost position, and from the ghost position to the maximal pair.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::ArenaAVLTree<u64,i32> = (0..3).map(|k| (k, 0)).collect();
+/// let mut cursor = t.upper_bound_cursor(Bound::Included(&1));
+/// cursor.move_prev();
+/// assert_eq!(cursor.key(), Some(&0));
+/// cursor.move_prev();
+/// assert_eq!(cursor.key(), None);
+/// cursor.move_prev();
+/// assert_eq!(cursor.key(), Some(&2));
+/// ```
+    pub fn move_prev(&mut self) {
+        match self.successor(-1) {
+            //go up until we leave a right successor, its parent is the previous node
+            NIL => while let Some(child) = self.path.pop() {
+                if self.path.last().is_some_and(|&parent| self.tree.successor(parent, 1) == child) { return }
+            },
+            left => self.push_spine(left, 1)
+        }
+    }
+
+/// This function will return the pair the cursor would point at after `move_next`, or None if that
+/// is the ghost position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::ArenaAVLTree<u64,i32> = (0..3).map(|k| (k, 0)).collect();
+/// let cursor = t.lower_bound_cursor(Bound::Excluded(&2));
+/// assert_eq!(cursor.key(), None);
+/// assert_eq!(cursor.peek_next(), Some((&0,&0)));
+/// assert_eq!(cursor.peek_prev(), Some((&2,&0)));
+/// ```
+    pub fn peek_next(&self) -> Option<(&'a K, &'a D)> {
+        let tree = self.tree;
+        match self.successor(1) {
+            NIL => self.deepest_parent(-1).map(|parent| tree.pair(parent)),
+            right => Some(tree.pair(tree.spine_end(right, -1)))
+        }
+    }
+
+/// This function will return the pair the cursor would point at after `move_prev`, or None if that
+/// is the ghost position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::ArenaAVLTree<u64,i32> = (0..3).map(|k| (k, 0)).collect();
+/// let cursor = t.lower_bound_cursor(Bound::Included(&0));
+/// assert_eq!(cursor.peek_prev(), None);
+/// assert_eq!(cursor.peek_next(), Some((&1,&0)));
+/// ```
+    pub fn peek_prev(&self) -> Option<(&'a K, &'a D)> {
+        let tree = self.tree;
+        match self.successor(-1) {
+            NIL => self.deepest_parent(1).map(|parent| tree.pair(parent)),
+            left => Some(tree.pair(tree.spine_end(left, 1)))
+        }
+    }
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> Clone for ArenaCursor<'a, K, D, P> {
+    fn clone(&self) -> ArenaCursor<'a, K, D, P> {
+        ArenaCursor{tree: self.tree, path: self.path.clone()}
+    }
+}
+
+/// A cursor over an `ArenaAVLTree` like `ArenaCursor`, which can also change the values, remove the
+/// pair it points at and insert new pairs next to it. Since every change may rotate the tree, it does
+/// not keep a path but only the slot of its pair, which an insertion does not move. Its neighbours are
+/// found by searching the key of its pair, which takes O(log n). It is returned by
+/// `ArenaAVLTree::lower_bound_cursor_mut` and `ArenaAVLTree::upper_bound_cursor_mut`.
+pub struct ArenaCursorMut<'a, K:'a+Ord, D:'a, P:'a+BalancePolicy=Avl> {
+    tree: &'a mut ArenaAVLTree<K,D,P>,
+    //the slot of the current pair, or NIL at the ghost position
+    current: u32,
+}
+
+/// returns a mutable cursor pointing at the minimal pair of `tree` above the bound `from`
+pub fn lower_bound_cursor_mut<'a, K:'a+Ord+Borrow<Q>, D:'a, P:BalancePolicy, Q:Ord+?Sized>(tree: &'a mut ArenaAVLTree<K,D,P>, from: Bound<&Q>) -> ArenaCursorMut<'a, K, D, P>{
+    let current = tree.first_above(from);
+    ArenaCursorMut{tree, current}
+}
+
+/// returns a mutable cursor pointing at the maximal pair of `tree` below the bound `to`
+pub fn upper_bound_cursor_mut<'a, K:'a+Ord+Borrow<Q>, D:'a, P:BalancePolicy, Q:Ord+?Sized>(tree: &'a mut ArenaAVLTree<K,D,P>, to: Bound<&Q>) -> ArenaCursorMut<'a, K, D, P>{
+    let current = tree.last_below(to);
+    ArenaCursorMut{tree, current}
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> ArenaCursorMut<'a, K, D, P> {
+
+    //at the ghost position the next pair is the minimal one
+    fn next_index(&self) -> u32 {
+        match self.current {
+            NIL => self.tree.spine_end(self.tree.root, -1),
+            current => self.tree.first_above(Bound::Excluded(self.tree.pair(current).0))
+        }
+    }
+
+    //at the ghost position the previous pair is the maximal one
+    fn prev_index(&self) -> u32 {
+        match self.current {
+            NIL => self.tree.spine_end(self.tree.root, 1),
+            current => self.tree.last_below(Bound::Excluded(self.tree.pair(current).0))
+        }
+    }
+
+    fn pair_at(&self, index: u32) -> Option<(&K, &D)> {
+        if index == NIL { None } else { Some(self.tree.pair(index)) }
+    }
+
+/// This function will return the key the cursor points at, or None at the ghost position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t : avl_tree::ArenaAVLTree<u64,i32> = (0..10).map(|k| (k*2, 0)).collect();
+/// assert_eq!(t.lower_bound_cursor_mut(Bound::Included(&5)).key(), Some(&6));
+/// assert_eq!(t.upper_bound_cursor_mut(Bound::Excluded(&0)).key(), None);
+/// ```
+    pub fn key(&self) -> Option<&K> {
+        self.pair_at(self.current).map(|(key, _)| key)
+    }
+
+/// This function will return the value the cursor points at, or None at the ghost position.
+    pub fn value(&self) -> Option<&D> {
+        self.pair_at(self.current).map(|(_, data)| data)
+    }
+
+/// This function will return a mutable reference to the value the cursor points at, or None at the
+/// ghost position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// *t.lower_bound_cursor_mut(Bound::Unbounded).value_mut().unwrap() += 1;
+/// assert_eq!(t.get(&2), Some(&26));
+/// ```
+    pub fn value_mut(&mut self) -> Option<&mut D> {
+        if self.current == NIL { None } else { Some(self.tree.data_mut(self.current)) }
+    }
+
+/// This function will move the cursor to the next pair. From the maximal pair it moves to the ghost
+/// position, and from the ghost position to the minimal pair.
+    pub fn move_next(&mut self) {
+        self.current = self.next_index();
+    }
+
+/// This function will move the cursor to the previous pair. From the minimal pair it moves to the
+/// ghost position, and from the ghost position to the maximal pair.
+    pub fn move_prev(&mut self) {
+        self.current = self.prev_index();
+    }
+
+/// This function will return the pair the cursor would point at after `move_next`, or None if that
+/// is the ghost position.
+    pub fn peek_next(&self) -> Option<(&K, &D)> {
+        self.pair_at(self.next_index())
+    }
+
+/// This function will return the pair the cursor would point at after `move_prev`, or None if that
+/// is the ghost position.
+    pub fn peek_prev(&self) -> Option<(&K, &D)> {
+        self.pair_at(self.prev_index())
+    }
+
+/// This function will remove the pair the cursor points at and return it, the cursor then points at
+/// the next pair. At the ghost position nothing is removed and None is returned.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t : avl_tree::ArenaAVLTree<u64,i32> = (0..5).map(|k| (k, 0)).collect();
+/// {
+///     let mut cursor = t.lower_bound_cursor_mut(Bound::Included(&1));
+///     assert_eq!(cursor.remove_current(), Some((1,0)));
+///     assert_eq!(cursor.remove_current(), Some((2,0)));
+///     assert_eq!(cursor.key(), Some(&3));
+/// }
+/// assert_eq!(t.iter().map(|(k,_)| *k).collect::<Vec<u64>>(), vec![0,3,4]);
+/// ```
+    pub fn remove_current(&mut self) -> Option<(K, D)> {
+        if self.current == NIL { return None }
+        let (path, _) = self.tree.search_path(self.tree.pair(self.current).0);
+        //a node with two successors takes over the next pair, so the next pair is searched again
+        let (key, data) = self.tree.remove_at(path);
+        self.current = self.tree.first_above(Bound::Excluded(&key));
+        Some((key, data))
+    }
+
+/// This function will insert the pair in front of the pair the cursor points at, the cursor keeps
+/// pointing at the same pair. At the ghost position the pair becomes the new maximum. If the key is
+/// not bigger than the key of the previous pair and smaller than the current one, the pair is handed
+/// back in an error.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t : avl_tree::ArenaAVLTree<u64,i32> = vec![(0,0), (10,0)].into_iter().collect();
+/// {
+///     let mut cursor = t.lower_bound_cursor_mut(Bound::Included(&10));
+///     cursor.insert_before(5, 1).unwrap();
+///     assert_eq!(cursor.key(), Some(&10));
+///     assert_eq!(cursor.peek_prev(), Some((&5,&1)));
+///     assert_eq!(cursor.insert_before(3, 2).unwrap_err().into_pair(), (3,2));
+/// }
+/// assert_eq!(t.len(), 3);
+/// ```
+    pub fn insert_before(&mut self, key: K, data: D) -> Result<(), CursorInsertError<K,D>> {
+        let fits = self.peek_prev().is_none_or(|(prev, _)| *prev < key) &&
+                   self.key().is_none_or(|current| key < *current);
+        if !fits { return Err(CursorInsertError::new(key, data)) }
+        self.tree.insert(key, data);
+        Ok(())
+    }
+
+/// This function will insert the pair behind the pair the cursor points at, the cursor keeps
+/// pointing at the same pair. At the ghost position the pair becomes the new minimum. If the key is
+/// not bigger than the current key and smaller than the key of the next pair, the pair is handed
+/// back in an error.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t : avl_tree::ArenaAVLTree<u64,i32> = vec![(0,0), (10,0)].into_iter().collect();
+/// {
+///     let mut cursor = t.upper_bound_cursor_mut(Bound::Included(&0));
+///     cursor.insert_after(5, 1).unwrap();
+///     assert_eq!(cursor.key(), Some(&0));
+///     assert_eq!(cursor.peek_next(), Some((&5,&1)));
+///     assert!(cursor.insert_after(10, 2).is_err());
+/// }
+/// assert_eq!(t.len(), 3);
+/// ```
+    pub fn insert_after(&mut self, key: K, data: D) -> Result<(), CursorInsertError<K,D>> {
+        let fits = self.key().is_none_or(|current| *current < key) &&
+                   self.peek_next().is_none_or(|(next, _)| key < *next);
+        if !fits { return Err(CursorInsertError::new(key, data)) }
+        self.tree.insert(key, data);
+        Ok(())
+    }
+}
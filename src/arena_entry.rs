@@ -0,0 +1,196 @@
+use ::arena::ArenaAVLTree;
+use ::balance::{Avl, BalancePolicy};
+use ::node::SearchPath;
+
+/// A view into a single key of an `ArenaAVLTree`, which is either occupied or vacant. It is returned
+/// by `ArenaAVLTree::entry`.
+pub enum ArenaEntry<'a, K:'a+Ord, D:'a, P:'a+BalancePolicy=Avl> {
+    Occupied(ArenaOccupiedEntry<'a, K, D, P>),
+    Vacant(ArenaVacantEntry<'a, K, D, P>),
+}
+
+/// A view into a key that is part of an `ArenaAVLTree`. It keeps the slot of the node, so its
+/// accessors do not search the tree, and the path to it, which a removal follows to rebalance.
+pub struct ArenaOccupiedEntry<'a, K:'a+Ord, D:'a, P:'a+BalancePolicy=Avl> {
+    tree: &'a mut ArenaAVLTree<K, D, P>,
+    index: u32,
+    path: SearchPath,
+}
+
+/// A view into a key that is not part of an `ArenaAVLTree`. It keeps the path to the empty successor
+/// where the key belongs, so an insertion does not compare keys again.
+pub struct ArenaVacantEntry<'a, K:'a+Ord, D:'a, P:'a+BalancePolicy=Avl> {
+    tree: &'a mut ArenaAVLTree<K, D, P>,
+    key: K,
+    path: SearchPath,
+}
+
+/// returns the entry for `key` in `tree`, which is searched once
+pub fn entry<'a, K:'a+Ord, D:'a, P:BalancePolicy>(tree: &'a mut ArenaAVLTree<K,D,P>, key: K) -> ArenaEntry<'a, K, D, P>{
+    let (path, found) = tree.search_path(&key);
+    match found {
+        Some(index) => ArenaEntry::Occupied(ArenaOccupiedEntry{tree, index, path}),
+        None => ArenaEntry::Vacant(ArenaVacantEntry{tree, key, path})
+    }
+}
+
+/// returns the entry of the minimal (side -1) or maximal (side 1) key in `tree`, or None if `tree`
+/// is empty
+pub fn spine_entry<'a, K:'a+Ord, D:'a, P:BalancePolicy>(tree: &'a mut ArenaAVLTree<K,D,P>, side: i8) -> Option<ArenaOccupiedEntry<'a, K, D, P>>{
+    let (path, index) = tree.spine_path(side);
+    index.map(move |index| ArenaOccupiedEntry{tree, index, path})
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> ArenaEntry<'a, K, D, P> {
+
+/// This function will call `f` on the data stored under the key of this entry if it is occupied and
+/// returns the entry for further use.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.entry(2).and_modify(|v| *v += 1).or_insert(25);
+/// assert_eq!(t.get(&2), Some(&25));
+/// t.entry(2).and_modify(|v| *v += 1).or_insert(25);
+/// assert_eq!(t.get(&2), Some(&26));
+/// ```
+    pub fn and_modify<F: FnOnce(&mut D)>(self, f: F) -> ArenaEntry<'a, K, D, P> {
+        match self {
+            ArenaEntry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                ArenaEntry::Occupied(entry)
+            },
+            ArenaEntry::Vacant(entry) => ArenaEntry::Vacant(entry)
+        }
+    }
+
+/// This function will return the key of this entry.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// assert_eq!(t.entry(2).key(), &2);
+/// ```
+    pub fn key(&self) -> &K {
+        match *self {
+            ArenaEntry::Occupied(ref entry) => entry.key(),
+            ArenaEntry::Vacant(ref entry) => entry.key()
+        }
+    }
+
+/// This function will return the data stored under the key of this entry, inserting `default`
+/// first if the entry is vacant.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// *t.entry(2).or_insert(0) += 5;
+/// *t.entry(2).or_insert(0) += 5;
+/// assert_eq!(t.get(&2), Some(&10));
+/// ```
+    pub fn or_insert(self, default: D) -> &'a mut D {
+        match self {
+            ArenaEntry::Occupied(entry) => entry.into_mut(),
+            ArenaEntry::Vacant(entry) => entry.insert(default)
+        }
+    }
+
+/// This function will return the data stored under the key of this entry, inserting the result of
+/// `default` first if the entry is vacant. `default` is only called for vacant entries.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,String>::new();
+/// t.entry(2).or_insert_with(|| "two".to_string()).push_str("!");
+/// assert_eq!(t.get(&2).unwrap(), "two!");
+/// ```
+    pub fn or_insert_with<F: FnOnce() -> D>(self, default: F) -> &'a mut D {
+        match self {
+            ArenaEntry::Occupied(entry) => entry.into_mut(),
+            ArenaEntry::Vacant(entry) => entry.insert(default())
+        }
+    }
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> ArenaOccupiedEntry<'a, K, D, P> {
+
+/// This function will return the key of this entry.
+    pub fn key(&self) -> &K {
+        self.tree.pair(self.index).0
+    }
+
+/// This function will return a read only reference to the data stored under the key of this entry.
+    pub fn get(&self) -> &D {
+        self.tree.pair(self.index).1
+    }
+
+/// This function will return a mutable reference to the data stored under the key of this entry.
+    pub fn get_mut(&mut self) -> &mut D {
+        self.tree.data_mut(self.index)
+    }
+
+/// This function will turn the entry into a mutable reference to its data that lives as long as
+/// the borrow of the tree.
+    pub fn into_mut(self) -> &'a mut D {
+        self.tree.data_mut(self.index)
+    }
+
+/// This function will replace the data stored under the key of this entry and return the old data.
+    pub fn insert(&mut self, data: D) -> D {
+        ::std::mem::replace(self.get_mut(), data)
+    }
+
+/// This function will remove the entry from the tree and return the data that was stored under its
+/// key.
+/// # Examples
+/// ```
+/// use avl_tree::ArenaEntry;
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// if let ArenaEntry::Occupied(entry) = t.entry(2) {
+///     assert_eq!(entry.remove(), 25);
+/// }
+/// assert!(t.empty());
+/// ```
+    pub fn remove(self) -> D {
+        self.remove_entry().1
+    }
+
+/// This function will remove the entry from the tree and return the key,value pair that was stored
+/// in it.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// assert_eq!(t.last_entry().unwrap().remove_entry(), (3,50));
+/// assert_eq!(t.len(), 1);
+/// ```
+    pub fn remove_entry(self) -> (K, D) {
+        self.tree.remove_at(self.path)
+    }
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> ArenaVacantEntry<'a, K, D, P> {
+
+/// This function will return the key of this entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+/// This function will insert the data under the key of this entry and return a mutable reference
+/// to it. The new node is inserted along the path that the entry was found by, so keys are not
+/// compared again.
+/// # Examples
+/// ```
+/// use avl_tree::ArenaEntry;
+/// let mut t=avl_tree::ArenaAVLTree::<u64,i32>::new();
+/// t.insert(1,10);
+/// if let ArenaEntry::Vacant(entry) = t.entry(2) {
+///     *entry.insert(25) += 1;
+/// }
+/// assert_eq!(t.get(&2), Some(&26));
+/// assert_eq!(t.len(), 2);
+/// ```
+    pub fn insert(self, data: D) -> &'a mut D {
+        let ArenaVacantEntry{tree, key, path} = self;
+        let index = tree.insert_at(key, data, path);
+        tree.data_mut(index)
+    }
+}
@@ -0,0 +1,32 @@
+use node::Rebalance;
+
+/// A balancing policy decides how an `AVLTree` or an `ArenaAVLTree` keeps its nodes balanced, all
+/// operations of the tree are built on top of it. The policies are zero sized types that select the
+/// algorithms and the balance information every node stores. The trait is sealed, it is implemented
+/// by `Avl`, `Wavl` and `WeightBalanced`.
+pub trait BalancePolicy: Rebalance {}
+
+/// The default policy, which keeps the heights of the successors of every node within one of each
+/// other. Insertions rotate at most once and removals up to O(log n) times, lookups visit at most
+/// 1.44 log n nodes. Every node stores the differences to the heights of its successors in one byte.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Avl;
+
+/// Weak AVL trees by Haeupler, Sen and Tarjan. They behave like AVL trees on insertion, but removals
+/// may leave both successors of a node two ranks below it, so a removal rotates at most twice. As
+/// long as nothing is removed the tree is an AVL tree, afterwards it is at most 2 log n high.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Wavl;
+
+/// Weight balanced trees, which balance the sizes of the successors of every node instead of their
+/// heights, neither may be more than three times as heavy as the other. Every node stores the size
+/// of its subtree in a usize, so `len` after a split is found in O(1) and the trees are at most
+/// 2.41 log n high, every insertion and removal rebalances the whole search path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct WeightBalanced;
+
+impl BalancePolicy for Avl {}
+
+impl BalancePolicy for Wavl {}
+
+impl BalancePolicy for WeightBalanced {}
@@ -0,0 +1,532 @@
+use ::tree::AVLTree;
+use ::balance::{BalancePolicy, Avl};
+use ::node::{self, Node, Link, SearchPath};
+use error::CursorInsertError;
+use std::borrow::Borrow;
+use std::ops::Bound;
+use std::ptr;
+
+/// A cursor over an `AVLTree` that points either at one of its pairs or at the ghost position, which
+/// lies behind the maximal and in front of the minimal pair. It keeps the path from the root to its
+/// pair, so moving to a neighbouring pair is amortized O(1). It is returned by
+/// `AVLTree::lower_bound_cursor` and `AVLTree::upper_bound_cursor`.
+pub struct Cursor<'a, K:'a+Ord, D:'a, P:'a+BalancePolicy=Avl> {
+    root: &'a Option<Box<Node<K,D,P>>>,
+    path: Vec<&'a Node<K,D,P>>,
+}
+
+/// returns a cursor pointing at the minimal pair of `tree` above the bound `from`
+pub fn lower_bound_cursor<'a, K:'a+Ord+Borrow<Q>, D:'a, P:BalancePolicy, Q:Ord+?Sized>(tree: &'a AVLTree<K,D,P>, from: Bound<&Q>) -> Cursor<'a, K, D, P>{
+    let mut path = Vec::new();
+    let mut target_len = 0;
+    let mut subtree = &tree.root;
+    while let Some(ref node) = *subtree {
+        let (key, _, left, right) = node::parts(node);
+        path.push(&**node);
+        if node::is_above_lower_bound(key, from) {
+            target_len = path.len();
+            subtree = left;
+        } else {
+            subtree = right;
+        }
+    }
+    path.truncate(target_len);
+    Cursor{root: &tree.root, path}
+}
+
+/// returns a cursor pointing at the maximal pair of `tree` below the bound `to`
+pub fn upper_bound_cursor<'a, K:'a+Ord+Borrow<Q>, D:'a, P:BalancePolicy, Q:Ord+?Sized>(tree: &'a AVLTree<K,D,P>, to: Bound<&Q>) -> Cursor<'a, K, D, P>{
+    let mut path = Vec::new();
+    let mut target_len = 0;
+    let mut subtree = &tree.root;
+    while let Some(ref node) = *subtree {
+        let (key, _, left, right) = node::parts(node);
+        path.push(&**node);
+        if node::is_below_upper_bound(key, to) {
+            target_len = path.len();
+            subtree = right;
+        } else {
+            subtree = left;
+        }
+    }
+    path.truncate(target_len);
+    Cursor{root: &tree.root, path}
+}
+
+//pushes the path from `subtree` to its minimal node
+fn push_min_path<'a, K:Ord, D, P:BalancePolicy>(path: &mut Vec<&'a Node<K,D,P>>, mut subtree: &'a Node<K,D,P>){
+    loop {
+        path.push(subtree);
+        match *node::parts(subtree).2 {
+            Some(ref left) => subtree = left,
+            None => return
+        }
+    }
+}
+
+//pushes the path from `subtree` to its maximal node
+fn push_max_path<'a, K:Ord, D, P:BalancePolicy>(path: &mut Vec<&'a Node<K,D,P>>, mut subtree: &'a Node<K,D,P>){
+    loop {
+        path.push(subtree);
+        match *node::parts(subtree).3 {
+            Some(ref right) => subtree = right,
+            None => return
+        }
+    }
+}
+
+fn is_left_successor<K:Ord, D, P:BalancePolicy>(parent: &Node<K,D,P>, child: &Node<K,D,P>) -> bool {
+    node::parts(parent).2.as_ref().is_some_and(|left| ptr::eq(&**left, child))
+}
+
+fn is_right_successor<K:Ord, D, P:BalancePolicy>(parent: &Node<K,D,P>, child: &Node<K,D,P>) -> bool {
+    node::parts(parent).3.as_ref().is_some_and(|right| ptr::eq(&**right, child))
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> Cursor<'a, K, D, P> {
+
+/// This function will return the key the cursor points at, or None at the ghost position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::AVLTree<u64,i32> = (0..10).map(|k| (k*2, 0)).collect();
+/// assert_eq!(t.lower_bound_cursor(Bound::Included(&5)).key(), Some(&6));
+/// assert_eq!(t.lower_bound_cursor(Bound::Excluded(&18)).key(), None);
+/// ```
+    pub fn key(&self) -> Option<&'a K> {
+        self.path.last().map(|node| node::parts(node).0)
+    }
+
+/// This function will return the value the cursor points at, or None at the ghost position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// assert_eq!(t.upper_bound_cursor(Bound::Included(&2)).value(), Some(&25));
+/// assert_eq!(t.upper_bound_cursor(Bound::Excluded(&2)).value(), None);
+/// ```
+    pub fn value(&self) -> Option<&'a D> {
+        self.path.last().map(|node| node::parts(node).1)
+    }
+
+/// This function will move the cursor to the next pair. From the maximal pair it moves to the ghost
+/// position, and from the ghost position to the minimal pair.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::AVLTree<u64,i32> = (0..3).map(|k| (k, 0)).collect();
+/// let mut cursor = t.lower_bound_cursor(Bound::Included(&1));
+/// cursor.move_next();
+/// assert_eq!(cursor.key(), Some(&2));
+/// cursor.move_next();
+/// assert_eq!(cursor.key(), None);
+/// cursor.move_next();
+/// assert_eq!(cursor.key(), Some(&0));
+/// ```
+    pub fn move_next(&mut self) {
+        let right = match self.path.last() {
+            Some(node) => node::parts(node).3,
+            None => self.root
+        };
+        match *right {
+            Some(ref right) => push_min_path(&mut self.path, right),
+            //go up until we leave a left successor, its parent is the next node
+            None => while let Some(child) = self.path.pop() {
+                if self.path.last().is_some_and(|parent| is_left_successor(parent, child)) { return }
+            }
+        }
+    }
+
+/// This function will move the cursor to the previous pair. From the minimal pair it moves to the
+/// ghost position, and from the ghost position to the maximal pair.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::AVLTree<u64,i32> = (0..3).map(|k| (k, 0)).collect();
+/// let mut cursor = t.upper_bound_cursor(Bound::Included(&1));
+/// cursor.move_prev();
+/// assert_eq!(cursor.key(), Some(&0));
+/// cursor.move_prev();
+/// assert_eq!(cursor.key(), None);
+/// cursor.move_prev();
+/// assert_eq!(cursor.key(), Some(&2));
+/// ```
+    pub fn move_prev(&mut self) {
+        let left = match self.path.last() {
+            Some(node) => node::parts(node).2,
+            None => self.root
+        };
+        match *left {
+            Some(ref left) => push_max_path(&mut self.path, left),
+            //go up until we leave a right successor, its parent is the previous node
+            None => while let Some(child) = self.path.pop() {
+                if self.path.last().is_some_and(|parent| is_right_successor(parent, child)) { return }
+            }
+        }
+    }
+
+/// This function will return the pair the cursor would point at after `move_next`, or None if that
+/// is the ghost position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::AVLTree<u64,i32> = (0..3).map(|k| (k, 0)).collect();
+/// let cursor = t.lower_bound_cursor(Bound::Excluded(&2));
+/// assert_eq!(cursor.key(), None);
+/// assert_eq!(cursor.peek_next(), Some((&0,&0)));
+/// assert_eq!(cursor.peek_prev(), Some((&2,&0)));
+/// ```
+    pub fn peek_next(&self) -> Option<(&'a K, &'a D)> {
+        let right = match self.path.last() {
+            Some(node) => node::parts(node).3,
+            None => self.root
+        };
+        match *right {
+            Some(ref right) => Some(node::min_pair(right)),
+            None => self.path.windows(2).rev()
+                .find(|pair| is_left_successor(pair[0], pair[1]))
+                .map(|pair| { let (key, data, _, _) = node::parts(pair[0]); (key, data) })
+        }
+    }
+
+/// This function will return the pair the cursor would point at after `move_prev`, or None if that
+/// is the ghost position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::AVLTree<u64,i32> = (0..3).map(|k| (k, 0)).collect();
+/// let cursor = t.lower_bound_cursor(Bound::Included(&0));
+/// assert_eq!(cursor.peek_prev(), None);
+/// assert_eq!(cursor.peek_next(), Some((&1,&0)));
+/// ```
+    pub fn peek_prev(&self) -> Option<(&'a K, &'a D)> {
+        let left = match self.path.last() {
+            Some(node) => node::parts(node).2,
+            None => self.root
+        };
+        match *left {
+            Some(ref left) => Some(node::max_pair(left)),
+            None => self.path.windows(2).rev()
+                .find(|pair| is_right_successor(pair[0], pair[1]))
+                .map(|pair| { let (key, data, _, _) = node::parts(pair[0]); (key, data) })
+        }
+    }
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> Clone for Cursor<'a, K, D, P> {
+    fn clone(&self) -> Cursor<'a, K, D, P> {
+        Cursor{root: self.root, path: self.path.clone()}
+    }
+}
+
+/// A cursor over an `AVLTree` like `Cursor`, which can also change the values, remove the pair it
+/// points at and insert new pairs next to it. It takes the tree apart along the path from the root to
+/// its pair: the nodes above its pair are kept on a stack, each without the successor the path
+/// continues with, so moving to a neighbouring pair is amortized O(1) like for `Cursor`. Before a
+/// pair is inserted or removed the tree is put back together, which takes O(log n), and the cursor
+/// finds its position again afterwards. The tree is put back together when the cursor is dropped.
+/// It is returned by `AVLTree::lower_bound_cursor_mut` and `AVLTree::upper_bound_cursor_mut`.
+#[allow(clippy::type_complexity)]
+pub struct CursorMut<'a, K:'a+Ord, D:'a, P:'a+BalancePolicy=Avl> {
+    tree: &'a mut AVLTree<K,D,P>,
+    //the nodes on the path from the root to the current node, each without its successor on the side
+    //the path takes from it (-1 for left, 1 for right)
+    above: Vec<(Box<Node<K,D,P>>, i8)>,
+    //the subtree below the current node, or the whole tree at the ghost position
+    current: Link<K,D,P>,
+    ghost: bool,
+}
+
+/// returns a mutable cursor pointing at the minimal pair of `tree` above the bound `from`
+pub fn lower_bound_cursor_mut<'a, K:'a+Ord+Borrow<Q>, D:'a, P:BalancePolicy, Q:Ord+?Sized>(tree: &'a mut AVLTree<K,D,P>, from: Bound<&Q>) -> CursorMut<'a, K, D, P>{
+    let mut cursor = CursorMut::new(tree);
+    cursor.seek(|key| node::is_above_lower_bound(key, from), -1);
+    cursor
+}
+
+/// returns a mutable cursor pointing at the maximal pair of `tree` below the bound `to`
+pub fn upper_bound_cursor_mut<'a, K:'a+Ord+Borrow<Q>, D:'a, P:BalancePolicy, Q:Ord+?Sized>(tree: &'a mut AVLTree<K,D,P>, to: Bound<&Q>) -> CursorMut<'a, K, D, P>{
+    let mut cursor = CursorMut::new(tree);
+    cursor.seek(|key| node::is_below_upper_bound(key, to), 1);
+    cursor
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> CursorMut<'a, K, D, P> {
+
+    //takes the whole tree, the cursor starts at the ghost position
+    fn new(tree: &'a mut AVLTree<K,D,P>) -> CursorMut<'a, K, D, P> {
+        let current = tree.root.take();
+        CursorMut{tree, above: Vec::new(), current, ghost: true}
+    }
+
+    //returns the successor of the current node on `side` (-1 for left, 1 for right)
+    fn successor(&self, side: i8) -> &Link<K,D,P> {
+        let (_, _, left, right) = node::parts(self.current.as_ref().expect("AVL broken"));
+        if side > 0 { right } else { left }
+    }
+
+    //moves down to the successor of the current node on `side`, which may be empty
+    fn descend(&mut self, side: i8) {
+        let mut node = self.current.take().expect("AVL broken");
+        self.current = {
+            let (_, _, left, right) = node::parts_mut(&mut node);
+            if side > 0 { right.take() } else { left.take() }
+        };
+        self.above.push((node, side));
+    }
+
+    //moves down along the spine on `side` of the current subtree to its minimal or maximal node
+    fn descend_spine(&mut self, side: i8) {
+        while self.successor(side).is_some() { self.descend(side) }
+    }
+
+    //moves up to the parent of the current node and returns the side the current node hangs on, or
+    //None at the root
+    fn ascend(&mut self) -> Option<i8> {
+        let (mut parent, side) = self.above.pop()?;
+        {
+            let (_, _, left, right) = node::parts_mut(&mut parent);
+            *(if side > 0 { right } else { left }) = self.current.take();
+        }
+        self.current = Some(parent);
+        Some(side)
+    }
+
+    //puts the tree back together and moves to the ghost position
+    fn zip(&mut self) {
+        while self.ascend().is_some() {}
+        self.ghost = true;
+    }
+
+    //moves from the ghost position to the last node on the search path for which `in_bound` holds,
+    //below it the search goes towards `side`. If there is no such node, it stays at the ghost position.
+    fn seek<F: Fn(&K) -> bool>(&mut self, in_bound: F, side: i8) {
+        let mut target = None;
+        while let Some(ref node) = self.current {
+            let towards = if in_bound(node::parts(node).0) {
+                target = Some(self.above.len());
+                side
+            } else {
+                -side
+            };
+            self.descend(towards);
+        }
+        match target {
+            Some(depth) => {
+                while self.above.len() > depth { self.ascend(); }
+                self.ghost = false;
+            },
+            None => self.zip()
+        }
+    }
+
+    //moves from the ghost position to the node the search path ends in
+    fn seek_path(&mut self, search: SearchPath) {
+        for level in 0..search.depth() { self.descend(search.turn(level)) }
+        self.ghost = false;
+    }
+
+    //hands the tree back for an insertion or removal, the cursor is at the ghost position afterwards
+    fn release(&mut self) -> &mut AVLTree<K,D,P> {
+        self.zip();
+        self.tree.root = self.current.take();
+        self.tree
+    }
+
+    //takes the tree again after an insertion or removal
+    fn reclaim(&mut self) {
+        self.current = self.tree.root.take();
+    }
+
+/// This function will return the key the cursor points at, or None at the ghost position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t : avl_tree::AVLTree<u64,i32> = (0..10).map(|k| (k*2, 0)).collect();
+/// assert_eq!(t.lower_bound_cursor_mut(Bound::Included(&5)).key(), Some(&6));
+/// assert_eq!(t.upper_bound_cursor_mut(Bound::Excluded(&0)).key(), None);
+/// ```
+    pub fn key(&self) -> Option<&K> {
+        if self.ghost { return None }
+        self.current.as_ref().map(|node| node::parts(node).0)
+    }
+
+/// This function will return the value the cursor points at, or None at the ghost position.
+    pub fn value(&self) -> Option<&D> {
+        if self.ghost { return None }
+        self.current.as_ref().map(|node| node::parts(node).1)
+    }
+
+/// This function will return a mutable reference to the value the cursor points at, or None at the
+/// ghost position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// *t.lower_bound_cursor_mut(Bound::Unbounded).value_mut().unwrap() += 1;
+/// assert_eq!(t.get(&2), Some(&26));
+/// ```
+    pub fn value_mut(&mut self) -> Option<&mut D> {
+        if self.ghost { return None }
+        self.current.as_mut().map(|node| node::parts_mut(node).1)
+    }
+
+/// This function will move the cursor to the next pair. From the maximal pair it moves to the ghost
+/// position, and from the ghost position to the minimal pair.
+    pub fn move_next(&mut self) {
+        self.step(1)
+    }
+
+/// This function will move the cursor to the previous pair. From the minimal pair it moves to the
+/// ghost position, and from the ghost position to the maximal pair.
+    pub fn move_prev(&mut self) {
+        self.step(-1)
+    }
+
+    //moves to the next pair on `side` (-1 for the previous, 1 for the next pair)
+    fn step(&mut self, side: i8) {
+        if self.ghost {
+            if self.current.is_some() {
+                self.ghost = false;
+                self.descend_spine(-side);
+            }
+        } else if self.successor(side).is_some() {
+            self.descend(side);
+            self.descend_spine(-side);
+        } else {
+            //go up until we leave a successor on the other side, its parent is the next node
+            loop {
+                match self.ascend() {
+                    Some(from) if from == -side => return,
+                    Some(_) => {},
+                    None => {
+                        self.ghost = true;
+                        return
+                    }
+                }
+            }
+        }
+    }
+
+/// This function will return the pair the cursor would point at after `move_next`, or None if that
+/// is the ghost position.
+    pub fn peek_next(&self) -> Option<(&K, &D)> {
+        self.peek(1)
+    }
+
+/// This function will return the pair the cursor would point at after `move_prev`, or None if that
+/// is the ghost position.
+    pub fn peek_prev(&self) -> Option<(&K, &D)> {
+        self.peek(-1)
+    }
+
+    //returns the pair the cursor would point at after a step to `side`
+    fn peek(&self, side: i8) -> Option<(&K, &D)> {
+        let subtree = if self.ghost { &self.current } else { self.successor(side) };
+        match *subtree {
+            Some(ref subtree) if side > 0 => Some(node::min_pair(subtree)),
+            Some(ref subtree) => Some(node::max_pair(subtree)),
+            None if self.ghost => None,
+            None => self.above.iter().rev()
+                .find(|&&(_, from)| from == -side)
+                .map(|(node, _)| { let (key, data, _, _) = node::parts(node); (key, data) })
+        }
+    }
+
+/// This function will remove the pair the cursor points at and return it, the cursor then points at
+/// the next pair. At the ghost position nothing is removed and None is returned.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t : avl_tree::AVLTree<u64,i32> = (0..5).map(|k| (k, 0)).collect();
+/// {
+///     let mut cursor = t.lower_bound_cursor_mut(Bound::Included(&1));
+///     assert_eq!(cursor.remove_current(), Some((1,0)));
+///     assert_eq!(cursor.remove_current(), Some((2,0)));
+///     assert_eq!(cursor.key(), Some(&3));
+/// }
+/// assert_eq!(t.iter().map(|(k,_)| *k).collect::<Vec<u64>>(), vec![0,3,4]);
+/// ```
+    pub fn remove_current(&mut self) -> Option<(K, D)> {
+        if self.ghost { return None }
+        let search = SearchPath::from_turns(self.above.iter().map(|&(_, side)| side));
+        let (key, data) = self.release().remove_at(search);
+        self.reclaim();
+        self.seek(|other| node::is_above_lower_bound(other, Bound::Excluded(&key)), -1);
+        Some((key, data))
+    }
+
+/// This function will insert the pair in front of the pair the cursor points at, the cursor keeps
+/// pointing at the same pair. At the ghost position the pair becomes the new maximum. If the key is
+/// not bigger than the key of the previous pair and smaller than the current one, the pair is handed
+/// back in an error.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t : avl_tree::AVLTree<u64,i32> = vec![(0,0), (10,0)].into_iter().collect();
+/// {
+///     let mut cursor = t.lower_bound_cursor_mut(Bound::Included(&10));
+///     cursor.insert_before(5, 1).unwrap();
+///     assert_eq!(cursor.key(), Some(&10));
+///     assert_eq!(cursor.peek_prev(), Some((&5,&1)));
+///     assert_eq!(cursor.insert_before(3, 2).unwrap_err().into_pair(), (3,2));
+/// }
+/// assert_eq!(t.len(), 3);
+/// ```
+    pub fn insert_before(&mut self, key: K, data: D) -> Result<(), CursorInsertError<K,D>> {
+        let fits = self.peek_prev().is_none_or(|(prev, _)| *prev < key) &&
+                   self.key().is_none_or(|current| key < *current);
+        if !fits { return Err(CursorInsertError::new(key, data)) }
+        self.insert_next_to(key, data, 1);
+        Ok(())
+    }
+
+/// This function will insert the pair behind the pair the cursor points at, the cursor keeps
+/// pointing at the same pair. At the ghost position the pair becomes the new minimum. If the key is
+/// not bigger than the current key and smaller than the key of the next pair, the pair is handed
+/// back in an error.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t : avl_tree::AVLTree<u64,i32> = vec![(0,0), (10,0)].into_iter().collect();
+/// {
+///     let mut cursor = t.upper_bound_cursor_mut(Bound::Included(&0));
+///     cursor.insert_after(5, 1).unwrap();
+///     assert_eq!(cursor.key(), Some(&0));
+///     assert_eq!(cursor.peek_next(), Some((&5,&1)));
+///     assert!(cursor.insert_after(10, 2).is_err());
+/// }
+/// assert_eq!(t.len(), 3);
+/// ```
+    pub fn insert_after(&mut self, key: K, data: D) -> Result<(), CursorInsertError<K,D>> {
+        let fits = self.key().is_none_or(|current| *current < key) &&
+                   self.peek_next().is_none_or(|(next, _)| key < *next);
+        if !fits { return Err(CursorInsertError::new(key, data)) }
+        self.insert_next_to(key, data, -1);
+        Ok(())
+    }
+
+    //inserts a pair that fits next to the current one, which lies on `side` of the new pair. The
+    //insertion returns the path of the new node, from which the cursor steps back to its pair.
+    fn insert_next_to(&mut self, key: K, data: D, side: i8) {
+        let was_ghost = self.ghost;
+        let inserted = {
+            let tree = self.release();
+            let (search, _) = node::search_path(&key, &tree.root);
+            tree.insert_at(key, data, search).0
+        };
+        self.reclaim();
+        if !was_ghost {
+            self.seek_path(inserted);
+            self.step(side);
+        }
+    }
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> Drop for CursorMut<'a, K, D, P> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
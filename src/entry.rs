@@ -0,0 +1,202 @@
+use ::tree::AVLTree;
+use ::balance::{BalancePolicy, Avl};
+use ::node::{self, SearchPath};
+
+/// A view into a single key of an `AVLTree`, which is either occupied or vacant. It is returned by
+/// `AVLTree::entry`.
+pub enum Entry<'a, K:'a+Ord, D:'a, P:'a+BalancePolicy=Avl> {
+    Occupied(OccupiedEntry<'a, K, D, P>),
+    Vacant(VacantEntry<'a, K, D, P>),
+}
+
+/// A view into a key that is part of the tree. It keeps the path to the node of the key, so its
+/// accessors follow the path instead of comparing keys again.
+pub struct OccupiedEntry<'a, K:'a+Ord, D:'a, P:'a+BalancePolicy=Avl> {
+    tree: &'a mut AVLTree<K, D, P>,
+    search: SearchPath,
+}
+
+/// A view into a key that is not part of the tree. It keeps the path to the empty successor where
+/// the key belongs, so an insertion does not compare keys again.
+pub struct VacantEntry<'a, K:'a+Ord, D:'a, P:'a+BalancePolicy=Avl> {
+    tree: &'a mut AVLTree<K, D, P>,
+    key: K,
+    search: SearchPath,
+}
+
+/// returns the entry for `key` in `tree`, which is searched once
+pub fn entry<'a, K:'a+Ord, D:'a, P:BalancePolicy>(tree: &'a mut AVLTree<K,D,P>, key: K) -> Entry<'a, K, D, P>{
+    let (search, found) = node::search_path(&key, &tree.root);
+    if found {
+        Entry::Occupied(OccupiedEntry{tree, search})
+    } else {
+        Entry::Vacant(VacantEntry{tree, key, search})
+    }
+}
+
+/// returns the entry of the minimal key in `tree`, or None if `tree` is empty
+pub fn first_entry<'a, K:'a+Ord, D:'a, P:BalancePolicy>(tree: &'a mut AVLTree<K,D,P>) -> Option<OccupiedEntry<'a, K, D, P>>{
+    if tree.empty() { return None }
+    let search = node::spine_path(-1, &tree.root);
+    Some(OccupiedEntry{tree, search})
+}
+
+/// returns the entry of the maximal key in `tree`, or None if `tree` is empty
+pub fn last_entry<'a, K:'a+Ord, D:'a, P:BalancePolicy>(tree: &'a mut AVLTree<K,D,P>) -> Option<OccupiedEntry<'a, K, D, P>>{
+    if tree.empty() { return None }
+    let search = node::spine_path(1, &tree.root);
+    Some(OccupiedEntry{tree, search})
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> Entry<'a, K, D, P> {
+
+/// This function will call `f` on the data stored under the key of this entry if it is occupied and
+/// returns the entry for further use.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.entry(2).and_modify(|v| *v += 1).or_insert(25);
+/// assert_eq!(t.get(&2), Some(&25));
+/// t.entry(2).and_modify(|v| *v += 1).or_insert(25);
+/// assert_eq!(t.get(&2), Some(&26));
+/// ```
+    pub fn and_modify<F: FnOnce(&mut D)>(self, f: F) -> Entry<'a, K, D, P> {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            },
+            Entry::Vacant(entry) => Entry::Vacant(entry)
+        }
+    }
+
+/// This function will return the key of this entry.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// assert_eq!(t.entry(2).key(), &2);
+/// ```
+    pub fn key(&self) -> &K {
+        match *self {
+            Entry::Occupied(ref entry) => entry.key(),
+            Entry::Vacant(ref entry) => entry.key()
+        }
+    }
+
+/// This function will return the data stored under the key of this entry, inserting `default`
+/// first if the entry is vacant.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// *t.entry(2).or_insert(0) += 5;
+/// *t.entry(2).or_insert(0) += 5;
+/// assert_eq!(t.get(&2), Some(&10));
+/// ```
+    pub fn or_insert(self, default: D) -> &'a mut D {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default)
+        }
+    }
+
+/// This function will return the data stored under the key of this entry, inserting the result of
+/// `default` first if the entry is vacant. `default` is only called for vacant entries.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,String>::new();
+/// t.entry(2).or_insert_with(|| "two".to_string()).push_str("!");
+/// assert_eq!(t.get(&2).unwrap(), "two!");
+/// ```
+    pub fn or_insert_with<F: FnOnce() -> D>(self, default: F) -> &'a mut D {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default())
+        }
+    }
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> OccupiedEntry<'a, K, D, P> {
+
+/// This function will return the key of this entry.
+    pub fn key(&self) -> &K {
+        node::pair_at(self.search, &self.tree.root).0
+    }
+
+/// This function will return a read only reference to the data stored under the key of this entry.
+    pub fn get(&self) -> &D {
+        node::pair_at(self.search, &self.tree.root).1
+    }
+
+/// This function will return a mutable reference to the data stored under the key of this entry.
+    pub fn get_mut(&mut self) -> &mut D {
+        node::data_at_mut(self.search, &mut self.tree.root)
+    }
+
+/// This function will turn the entry into a mutable reference to its data that lives as long as
+/// the borrow of the tree.
+    pub fn into_mut(self) -> &'a mut D {
+        node::data_at_mut(self.search, &mut self.tree.root)
+    }
+
+/// This function will replace the data stored under the key of this entry and return the old data.
+    pub fn insert(&mut self, data: D) -> D {
+        ::std::mem::replace(self.get_mut(), data)
+    }
+
+/// This function will remove the entry from the tree and return the data that was stored under its
+/// key.
+/// # Examples
+/// ```
+/// use avl_tree::Entry;
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// if let Entry::Occupied(entry) = t.entry(2) {
+///     assert_eq!(entry.remove(), 25);
+/// }
+/// assert!(t.empty());
+/// ```
+    pub fn remove(self) -> D {
+        self.remove_entry().1
+    }
+
+/// This function will remove the entry from the tree and return the key,value pair that was stored
+/// in it.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// assert_eq!(t.last_entry().unwrap().remove_entry(), (3,50));
+/// assert_eq!(t.len(), 1);
+/// ```
+    pub fn remove_entry(self) -> (K, D) {
+        self.tree.remove_at(self.search)
+    }
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> VacantEntry<'a, K, D, P> {
+
+/// This function will return the key of this entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+/// This function will insert the data under the key of this entry and return a mutable reference
+/// to it. The new node is inserted along the path that the entry was found by and the reference is
+/// taken from the insertion, so keys are not compared again.
+/// # Examples
+/// ```
+/// use avl_tree::Entry;
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(1,10);
+/// if let Entry::Vacant(entry) = t.entry(2) {
+///     *entry.insert(25) += 1;
+/// }
+/// assert_eq!(t.get(&2), Some(&26));
+/// assert_eq!(t.len(), 2);
+/// ```
+    pub fn insert(self, data: D) -> &'a mut D {
+        let VacantEntry{tree, key, search} = self;
+        tree.insert_at(key, data, search).1
+    }
+}
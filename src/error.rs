@@ -0,0 +1,88 @@
+use std::error::Error;
+use std::fmt;
+
+/// The error returned by `AVLTree::from_sorted_iter` and `ArenaAVLTree::from_sorted_iter` if the
+/// keys of its input are not strictly ascending, that is if the input is not sorted or contains a
+/// key more than once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsortedError {
+    position: usize,
+}
+
+impl UnsortedError {
+
+    pub(crate) fn new(position: usize) -> UnsortedError {
+        UnsortedError{position}
+    }
+
+/// This function will return the position of the first pair in the input whose key is not bigger
+/// than the key of the pair before it.
+    pub fn position(&self) -> usize { self.position }
+}
+
+impl fmt::Display for UnsortedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the key at position {} is not bigger than the key before it", self.position)
+    }
+}
+
+impl Error for UnsortedError {}
+
+/// The error returned by `AVLTree::push_back` and `ArenaAVLTree::push_back` if the key is not
+/// bigger than all keys of the tree. It hands back the pair that was not inserted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushBackError<K, D> {
+    key: K,
+    data: D,
+}
+
+impl<K, D> PushBackError<K, D> {
+
+    pub(crate) fn new(key: K, data: D) -> PushBackError<K, D> {
+        PushBackError{key, data}
+    }
+
+/// This function will return the key that was not inserted.
+    pub fn key(&self) -> &K { &self.key }
+
+/// This function will return the key,value pair that was not inserted.
+    pub fn into_pair(self) -> (K, D) { (self.key, self.data) }
+}
+
+impl<K, D> fmt::Display for PushBackError<K, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the key is not bigger than the maximal key of the tree")
+    }
+}
+
+impl<K: fmt::Debug, D: fmt::Debug> Error for PushBackError<K, D> {}
+
+/// The error returned by `CursorMut::insert_before` and `CursorMut::insert_after` if the key does
+/// not lie strictly between the keys around the insert position. It hands back the pair that was not
+/// inserted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorInsertError<K, D> {
+    key: K,
+    data: D,
+}
+
+impl<K, D> CursorInsertError<K, D> {
+
+    pub(crate) fn new(key: K, data: D) -> CursorInsertError<K, D> {
+        CursorInsertError{key, data}
+    }
+
+/// This function will return the key that was not inserted.
+    pub fn key(&self) -> &K { &self.key }
+
+/// This function will return the key,value pair that was not inserted.
+    pub fn into_pair(self) -> (K, D) { (self.key, self.data) }
+}
+
+impl<K, D> fmt::Display for CursorInsertError<K, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the key does not lie strictly between the keys around the cursor")
+    }
+}
+
+impl<K: fmt::Debug, D: fmt::Debug> Error for CursorInsertError<K, D> {}
@@ -0,0 +1,158 @@
+use ::tree::AVLTree;
+use ::balance::{BalancePolicy, Avl};
+use ::node::{self, Heap, Node, Link};
+use error::PushBackError;
+use std::cmp::Ordering;
+use std::mem;
+
+/// A finger into an `AVLTree`, which inserts pairs starting from where it inserted the last pair
+/// instead of from the root. It takes the tree apart like `CursorMut`: the nodes on the path from
+/// the root to the finger are kept on a stack, each without the successor the path continues with.
+/// An insertion only goes up until the key lies between the keys around the subtree below the
+/// finger, walks down from there and rebalances upwards until the rank of a subtree stays the same,
+/// and the finger stays at that subtree. So `push_back` keeps the finger on the right spine and
+/// appends ascending keys in amortized O(1) under the rank balancing policies `Avl` and `Wavl`.
+/// `WeightBalanced` trees update the sizes of all nodes above a new pair, so there every insertion
+/// goes up to the root and takes O(log n). The tree is put back together when the finger is dropped.
+/// It is returned by `AVLTree::finger`.
+#[allow(clippy::type_complexity)]
+pub struct Finger<'a, K:'a+Ord, D:'a, P:'a+BalancePolicy=Avl> {
+    tree: &'a mut AVLTree<K,D,P>,
+    //the nodes on the path from the root to the finger, each without its successor on the side the
+    //path takes from it (-1 for left, 1 for right)
+    above: Vec<(Box<Node<K,D,P>>, i8)>,
+    //the positions in above of the nodes the path turns right and left at, the last one of each is
+    //the nearest key below and above all keys of the current subtree
+    lower: Vec<usize>,
+    upper: Vec<usize>,
+    //the subtree below the finger
+    current: Link<K,D,P>,
+}
+
+/// returns a finger at the root of `tree`
+pub fn finger<'a, K:'a+Ord, D:'a, P:BalancePolicy>(tree: &'a mut AVLTree<K,D,P>) -> Finger<'a, K, D, P>{
+    let current = tree.root.take();
+    Finger{tree, above: Vec::new(), lower: Vec::new(), upper: Vec::new(), current}
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> Finger<'a, K, D, P> {
+
+    //moves down to the successor of the current node on `side`, which may be empty
+    fn descend(&mut self, side: i8) {
+        let mut node = self.current.take().expect("AVL broken");
+        self.current = {
+            let (_, _, left, right) = node::parts_mut(&mut node);
+            if side > 0 { right.take() } else { left.take() }
+        };
+        if side > 0 { self.lower.push(self.above.len()) } else { self.upper.push(self.above.len()) }
+        self.above.push((node, side));
+    }
+
+    //moves up to the parent of the current subtree and returns the side the subtree hangs on, or
+    //None at the root
+    fn ascend(&mut self) -> Option<i8> {
+        let (mut parent, side) = self.above.pop()?;
+        if side > 0 { self.lower.pop(); } else { self.upper.pop(); }
+        {
+            let (_, _, left, right) = node::parts_mut(&mut parent);
+            *(if side > 0 { right } else { left }) = self.current.take();
+        }
+        self.current = Some(parent);
+        Some(side)
+    }
+
+    //returns the key of the node at the given position in above
+    fn key_above(&self, position: usize) -> &K {
+        node::parts(&self.above[position].0).0
+    }
+
+    //returns whether key lies between the keys around the current subtree, so that it belongs into it
+    fn encloses(&self, key: &K) -> bool {
+        self.lower.last().is_none_or(|&below| self.key_above(below) < key) &&
+        self.upper.last().is_none_or(|&over| key < self.key_above(over))
+    }
+
+    //puts a new node into the empty subtree below the finger and rebalances upwards, as long as the
+    //rank of the subtree below the finger grows
+    fn attach_leaf(&mut self, key: K, data: D) {
+        self.current = Some(Box::new(Node::new(key, data)));
+        self.tree.len += 1;
+        while let Some(side) = self.ascend() {
+            let node = self.current.take().expect("AVL broken");
+            let (node, grown) = P::rebalance_after_growth(&mut Heap, node, side);
+            self.current = Some(node);
+            if !grown { return }
+        }
+    }
+
+/// This function will insert the key,value pair into the tree, overwriting and returning the old
+/// data if the key is allready part of the tree. The search starts at the subtree where the last
+/// insertion ended and only goes up as far as needed, so inserting keys close to the last one is
+/// faster than `AVLTree::insert`.
+/// # Examples
+/// ```
+/// let mut t : avl_tree::AVLTree<u64,i32> = (0..10).map(|k| (k*10, 0)).collect();
+/// {
+///     let mut finger = t.finger();
+///     for key in 41..50 {
+///         assert_eq!(finger.insert(key, 1), None);
+///     }
+///     assert_eq!(finger.insert(45, 2), Some(1));
+/// }
+/// assert_eq!(t.len(), 19);
+/// assert_eq!(t.get(&45), Some(&2));
+/// ```
+    pub fn insert(&mut self, key: K, data: D) -> Option<D> {
+        while !self.encloses(&key) { self.ascend(); }
+        loop {
+            let order = match self.current {
+                Some(ref node) => node::parts(node).0.cmp(&key),
+                None => break
+            };
+            match order {
+                Ordering::Equal => {
+                    let (_, old, _, _) = node::parts_mut(self.current.as_mut().expect("AVL broken"));
+                    return Some(mem::replace(old, data))
+                },
+                Ordering::Less => self.descend(1),
+                Ordering::Greater => self.descend(-1)
+            }
+        }
+        self.attach_leaf(key, data);
+        None
+    }
+
+/// This function will insert the key,value pair as new maximum of the tree. The finger goes up to
+/// the right spine and walks down to its end without comparing keys, and stays on the right spine
+/// afterwards. So appending ascending keys like timestamps or sequence numbers takes amortized O(1)
+/// under the policies `Avl` and `Wavl`, and O(log n) under `WeightBalanced`. If the key is not
+/// bigger than all keys of the tree, the pair is handed back in an error.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// {
+///     let mut finger = t.finger();
+///     for key in 0..1000 {
+///         finger.push_back(key, 0).unwrap();
+///     }
+///     assert_eq!(finger.push_back(500, 1).unwrap_err().into_pair(), (500,1));
+/// }
+/// assert_eq!(t.len(), 1000);
+/// assert_eq!(t.last_key_value(), Some((&999,&0)));
+/// ```
+    pub fn push_back(&mut self, key: K, data: D) -> Result<(), PushBackError<K,D>> {
+        while !self.upper.is_empty() { self.ascend(); }
+        while self.current.is_some() { self.descend(1) }
+        let fits = self.above.last().is_none_or(|(max, _)| *node::parts(max).0 < key);
+        if !fits { return Err(PushBackError::new(key, data)) }
+        self.attach_leaf(key, data);
+        Ok(())
+    }
+}
+
+impl<'a, K:'a+Ord, D:'a, P:BalancePolicy> Drop for Finger<'a, K, D, P> {
+    fn drop(&mut self) {
+        while self.ascend().is_some() {}
+        self.tree.root = self.current.take();
+    }
+}
@@ -1,60 +1,347 @@
 use ::tree;
 use ::node;
-use std::collections::Bound;
+use ::balance::{BalancePolicy, Avl};
+use std::borrow::Borrow;
+use std::ops::Bound;
+#[cfg(test)]
+use std::ops::RangeBounds;
 
-pub struct RangePairIter<'a, K:'a+Ord+Copy,D:'a> {
-    tree: &'a tree::AVLTree<K, D>,
-    from: Bound<K>,
-    to: Bound<K>,
-    prev: Option<&'a K>,
+/// Iterator over (key,value) pairs of a tree within the given bounds. It can be consumed from both
+/// ends. Each end keeps the nodes on the path to its next pair that have not been returned yet on a
+/// stack, the top of the stack is the next pair. Every node is pushed at most once per end, so a
+/// full scan is O(n) and `next` is amortized O(1).
+pub struct RangePairIter<'a, K:'a+Ord,D:'a,P:'a+BalancePolicy=Avl> {
+    front: Vec<&'a node::Node<K,D,P>>,
+    back: Vec<&'a node::Node<K,D,P>>,
 }
 
-impl<'a, K:'a+Ord+Copy,D:'a> RangePairIter<'a, K, D> {
+impl<'a, K:'a+Ord,D:'a,P:BalancePolicy> RangePairIter<'a, K, D, P> {
 
-    pub fn new(tree: &'a tree::AVLTree<K,D>, lower: Bound<K>, upper: Bound<K>) -> RangePairIter<'a,K,D>{
-        RangePairIter{tree: tree, from: lower, to: upper, prev:None}
+    pub(crate) fn new<Q:Ord+?Sized>(tree: &'a tree::AVLTree<K,D,P>, lower: Bound<&Q>, upper: Bound<&Q>) -> RangePairIter<'a,K,D,P> where K: Borrow<Q> {
+        let mut iter = RangePairIter{front: Vec::new(), back: Vec::new()};
+        push_lower_bound_nodes(&mut iter.front, &tree.root, lower);
+        push_upper_bound_nodes(&mut iter.back, &tree.root, upper);
+        match (iter.front.last(), iter.back.last()) {
+            (Some(first), Some(last)) if node::parts(first).0 <= node::parts(last).0 => iter,
+            _ => RangePairIter{front: Vec::new(), back: Vec::new()}
+        }
     }
 
-    fn get_next_key_under(&mut self, root: &'a Box<node::Node<K,D>>) -> Option<(&'a K,&'a D)>{
-        let res = self.get_next_pair(root).and_then(|p| self.check_upper_bound(p));
-        if let Some((key,_)) = res { self.prev = Some(key) }
-        return res
+    //front and back meet once they point to the same pair, after returning it the range is exhausted
+    fn is_last_pair(other_end: &[&'a node::Node<K,D,P>], current: &K) -> bool {
+        other_end.last().is_some_and(|other| node::parts(other).0 == current)
     }
 
-    fn get_next_pair(&mut self, root: &'a Box<node::Node<K,D>>) -> Option<(&'a K, &'a D)>{
-        match self.prev{
-            None => self.get_lower_bound_pair(root),
-            Some(key) => node::min_after::<K,D>(key, root)
-        }
+    fn exhaust(&mut self) {
+        self.front.clear();
+        self.back.clear();
     }
+}
 
-    fn get_lower_bound_pair(&self, root: &'a Box<node::Node<K,D>>) -> Option<(&'a K, &'a D)>{
-        match self.from {
-            Bound::Included(ref key) => node::search_pair(key, root).or_else(|| node::min_after(key, root)),
-            Bound::Excluded(ref key) => node::min_after(key, root),
-            Bound::Unbounded => Some(node::min_pair(root))
+//pushes all nodes on the search path for the lower bound that are not below the lower bound, the
+//minimal pair within the bound ends up on top
+fn push_lower_bound_nodes<'a, K:Ord+Borrow<Q>,D,P:BalancePolicy,Q:Ord+?Sized>(stack: &mut Vec<&'a node::Node<K,D,P>>, mut subtree: &'a Option<Box<node::Node<K,D,P>>>, from: Bound<&Q>){
+    while let Some(ref node) = *subtree {
+        let (key, _, left, right) = node::parts(node);
+        if node::is_above_lower_bound(key, from) {
+            stack.push(node);
+            subtree = left;
+        } else {
+            subtree = right;
         }
     }
+}
 
-    fn check_upper_bound(&self, current: (&'a K, &'a D)) -> Option<(&'a K, &'a D)> {
-        let ok = match self.to {
-            Bound::Included(ref key) => current.0 <= key,
-            Bound::Excluded(ref key) => current.0 < key,
-            Bound::Unbounded => true
-        };
-        return if ok { Some(current) } else { None };
+//pushes all nodes on the search path for the upper bound that are not above the upper bound, the
+//maximal pair within the bound ends up on top
+fn push_upper_bound_nodes<'a, K:Ord+Borrow<Q>,D,P:BalancePolicy,Q:Ord+?Sized>(stack: &mut Vec<&'a node::Node<K,D,P>>, mut subtree: &'a Option<Box<node::Node<K,D,P>>>, to: Bound<&Q>){
+    while let Some(ref node) = *subtree {
+        let (key, _, left, right) = node::parts(node);
+        if node::is_below_upper_bound(key, to) {
+            stack.push(node);
+            subtree = right;
+        } else {
+            subtree = left;
+        }
     }
 }
 
-impl<'a, K:'a+Ord+Copy,D:'a> Iterator for RangePairIter<'a, K, D> {
+impl<'a, K:'a+Ord,D:'a,P:BalancePolicy> Iterator for RangePairIter<'a, K, D, P> {
 
     type Item = (&'a K,&'a D);
 
     fn next(&mut self) -> Option<(&'a K,&'a D)> {
-        self.tree.root.as_ref().map_or(None,|node| self.get_next_key_under(node))
+        let (key, data, _, right) = node::parts(self.front.pop()?);
+        if Self::is_last_pair(&self.back, key) {
+            self.exhaust();
+        } else {
+            push_lower_bound_nodes::<K,D,P,K>(&mut self.front, right, Bound::Unbounded);
+        }
+        Some((key, data))
+    }
+}
+
+impl<'a, K:'a+Ord,D:'a,P:BalancePolicy> DoubleEndedIterator for RangePairIter<'a, K, D, P> {
+
+    fn next_back(&mut self) -> Option<(&'a K,&'a D)> {
+        let (key, data, left, _) = node::parts(self.back.pop()?);
+        if Self::is_last_pair(&self.front, key) {
+            self.exhaust();
+        } else {
+            push_upper_bound_nodes::<K,D,P,K>(&mut self.back, left, Bound::Unbounded);
+        }
+        Some((key, data))
+    }
+}
+
+/// Iterator over (key,value) pairs of a tree within the given bounds, handing out mutable references
+/// to the values. Both bounds are resolved when the iterator is created: the nodes on the search
+/// paths of the bounds are split into their parts, and the range is kept as a stack of single pairs
+/// and of whole subtrees that lie completely within the range.
+pub struct RangePairIterMut<'a, K:'a+Ord,D:'a,P:'a+BalancePolicy=Avl> {
+    stack: Vec<Pending<'a, K, D, P>>,
+}
+
+//A part of the range that still has to be visited
+enum Pending<'a, K:'a+Ord, D:'a, P:'a+BalancePolicy> {
+    Pair(&'a K, &'a mut D),
+    Subtree(&'a mut Option<Box<node::Node<K,D,P>>>),
+}
+
+impl<'a, K:'a+Ord,D:'a,P:BalancePolicy> RangePairIterMut<'a, K, D, P> {
+
+    pub(crate) fn new<Q:Ord+?Sized>(tree: &'a mut tree::AVLTree<K,D,P>, lower: Bound<&Q>, upper: Bound<&Q>) -> RangePairIterMut<'a,K,D,P> where K: Borrow<Q> {
+        let mut stack = Vec::new();
+        let mut subtree = &mut tree.root;
+        //find the topmost node within the range, the paths of both bounds split up below it
+        while let Some(node) = subtree.as_mut() {
+            let (key, data, left, right) = node::parts_mut(node);
+            if !node::is_above_lower_bound(key, lower) {
+                subtree = right;
+            } else if !node::is_below_upper_bound(key, upper) {
+                subtree = left;
+            } else {
+                push_upper_bound_path(&mut stack, right, upper);
+                stack.push(Pending::Pair(key, data));
+                push_lower_bound_path(&mut stack, left, lower);
+                break;
+            }
+        }
+        RangePairIterMut{stack}
+    }
+}
+
+//pushes all nodes on the search path for the lower bound that are not below the lower bound, each
+//followed by its right successor (which is completely within the range)
+fn push_lower_bound_path<'a, K:Ord+Borrow<Q>,D,P:BalancePolicy,Q:Ord+?Sized>(stack: &mut Vec<Pending<'a, K, D, P>>, mut subtree: &'a mut Option<Box<node::Node<K,D,P>>>, from: Bound<&Q>){
+    while let Some(node) = subtree.as_mut() {
+        let (key, data, left, right) = node::parts_mut(node);
+        if node::is_above_lower_bound(key, from) {
+            if right.is_some() { stack.push(Pending::Subtree(right)) }
+            stack.push(Pending::Pair(key, data));
+            subtree = left;
+        } else {
+            subtree = right;
+        }
+    }
+}
+
+//pushes all nodes on the search path for the upper bound that are not above the upper bound, each
+//preceded by its left successor (which is completely within the range)
+fn push_upper_bound_path<'a, K:Ord+Borrow<Q>,D,P:BalancePolicy,Q:Ord+?Sized>(stack: &mut Vec<Pending<'a, K, D, P>>, mut subtree: &'a mut Option<Box<node::Node<K,D,P>>>, to: Bound<&Q>){
+    let mut parts = Vec::new();
+    while let Some(node) = subtree.as_mut() {
+        let (key, data, left, right) = node::parts_mut(node);
+        if node::is_below_upper_bound(key, to) {
+            if left.is_some() { parts.push(Pending::Subtree(left)) }
+            parts.push(Pending::Pair(key, data));
+            subtree = right;
+        } else {
+            subtree = left;
+        }
     }
+    stack.extend(parts.into_iter().rev());
+}
+
+impl<'a, K:'a+Ord,D:'a,P:BalancePolicy> Iterator for RangePairIterMut<'a, K, D, P> {
 
+    type Item = (&'a K,&'a mut D);
 
+    fn next(&mut self) -> Option<(&'a K,&'a mut D)> {
+        loop {
+            match self.stack.pop() {
+                Some(Pending::Pair(key, data)) => return Some((key, data)),
+                Some(Pending::Subtree(subtree)) => push_lower_bound_path::<K,D,P,K>(&mut self.stack, subtree, Bound::Unbounded),
+                None => return None
+            }
+        }
+    }
+}
+
+/// Iterator over mutable references to the values of a tree, ordered by their keys.
+pub struct ValuesMut<'a, K:'a+Ord,D:'a,P:'a+BalancePolicy=Avl> {
+    inner: RangePairIterMut<'a, K, D, P>,
+}
+
+impl<'a, K:'a+Ord,D:'a,P:BalancePolicy> ValuesMut<'a, K, D, P> {
+    pub(crate) fn new(inner: RangePairIterMut<'a, K, D, P>) -> ValuesMut<'a, K, D, P>{
+        ValuesMut{inner}
+    }
+}
+
+impl<'a, K:'a+Ord,D:'a,P:BalancePolicy> Iterator for ValuesMut<'a, K, D, P> {
+
+    type Item = &'a mut D;
+
+    fn next(&mut self) -> Option<&'a mut D> {
+        self.inner.next().map(|(_, data)| data)
+    }
+}
+
+/// Iterator that consumes a tree and yields its owned (key,value) pairs ordered by key. The nodes
+/// that still have to be visited are kept on a stack, together with their right successors.
+pub struct IntoIter<K:Ord,D,P:BalancePolicy=Avl> {
+    stack: Vec<(K, D, node::Link<K,D,P>)>,
+    remaining: usize,
+}
+
+impl<K:Ord,D,P:BalancePolicy> IntoIter<K, D, P> {
+
+    pub(crate) fn new(root: Option<Box<node::Node<K,D,P>>>, len: usize) -> IntoIter<K,D,P>{
+        let mut iter = IntoIter{stack: Vec::new(), remaining: len};
+        iter.push_left_spine(root);
+        iter
+    }
+
+    //pushes the path to the minimal node of subtree
+    fn push_left_spine(&mut self, mut subtree: Option<Box<node::Node<K,D,P>>>){
+        while let Some(node) = subtree {
+            let (key, data, left, right) = node::into_parts(*node);
+            self.stack.push((key, data, right));
+            subtree = left;
+        }
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy> Iterator for IntoIter<K, D, P> {
+
+    type Item = (K,D);
+
+    fn next(&mut self) -> Option<(K,D)> {
+        let (key, data, right) = self.stack.pop()?;
+        self.push_left_spine(right);
+        self.remaining -= 1;
+        Some((key, data))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// Iterator that removes the (key,value) pairs within a range of a tree for which a predicate
+/// returns true and yields them in ascending order of the keys. It is returned by
+/// `AVLTree::extract_if`. The range is split off the tree when the iterator is created, every call
+/// of `next` runs the predicate on the following pairs until it returns true. When the iterator is
+/// dropped, the kept pairs and those that were not visited are rebuilt into a tree and joined back
+/// in, so pairs are only removed as far as the iterator was consumed, also if the predicate panics.
+pub struct ExtractIf<'a, K:'a+Ord, D:'a, F, P:'a+BalancePolicy=Avl> {
+    tree: &'a mut tree::AVLTree<K,D,P>,
+    //the parts of the tree below and above the range
+    before: node::Link<K,D,P>,
+    after: node::Link<K,D,P>,
+    //the visited pairs of the range that were kept, and the pairs that were not visited yet
+    kept: Vec<(K,D)>,
+    rest: IntoIter<K,D,P>,
+    pred: F,
+}
+
+impl<'a, K:'a+Ord, D:'a, F, P:BalancePolicy> ExtractIf<'a, K, D, F, P> {
+
+    pub(crate) fn new(tree: &'a mut tree::AVLTree<K,D,P>, before: node::Link<K,D,P>, rest: IntoIter<K,D,P>, after: node::Link<K,D,P>, pred: F) -> ExtractIf<'a,K,D,F,P>{
+        ExtractIf{tree, before, after, kept: Vec::new(), rest, pred}
+    }
+}
+
+impl<'a, K:'a+Ord, D:'a, F:FnMut(&K, &mut D) -> bool, P:BalancePolicy> Iterator for ExtractIf<'a, K, D, F, P> {
+
+    type Item = (K,D);
+
+    fn next(&mut self) -> Option<(K,D)> {
+        for pair in self.rest.by_ref() {
+            //the pair is kept until pred returns, so a panicking pred does not lose it
+            self.kept.push(pair);
+            let (key, data) = self.kept.last_mut().expect("pushed above");
+            if (self.pred)(key, data) {
+                return self.kept.pop()
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.rest.size_hint().1)
+    }
+}
+
+impl<'a, K:'a+Ord, D:'a, F, P:BalancePolicy> Drop for ExtractIf<'a, K, D, F, P> {
+    fn drop(&mut self) {
+        let mut kept = ::std::mem::take(&mut self.kept);
+        kept.extend(&mut self.rest);
+        let len = kept.len();
+        let range = node::from_sorted::<P,_,_,_>(&mut kept.into_iter(), len);
+        let below = node::concat_optional::<P,_,_>(self.before.take(), range);
+        self.tree.root = node::concat_optional::<P,_,_>(below, self.after.take());
+        self.tree.len += len;
+    }
+}
+
+/// Iterator that consumes a tree and yields its owned keys in order.
+pub struct IntoKeys<K:Ord,D,P:BalancePolicy=Avl> {
+    inner: IntoIter<K, D, P>,
+}
+
+impl<K:Ord,D,P:BalancePolicy> IntoKeys<K, D, P> {
+    pub(crate) fn new(inner: IntoIter<K, D, P>) -> IntoKeys<K, D, P>{
+        IntoKeys{inner}
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy> Iterator for IntoKeys<K, D, P> {
+
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Iterator that consumes a tree and yields its owned values ordered by their keys.
+pub struct IntoValues<K:Ord,D,P:BalancePolicy=Avl> {
+    inner: IntoIter<K, D, P>,
+}
+
+impl<K:Ord,D,P:BalancePolicy> IntoValues<K, D, P> {
+    pub(crate) fn new(inner: IntoIter<K, D, P>) -> IntoValues<K, D, P>{
+        IntoValues{inner}
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy> Iterator for IntoValues<K, D, P> {
+
+    type Item = D;
+
+    fn next(&mut self) -> Option<D> {
+        self.inner.next().map(|(_, data)| data)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
 }
 
 #[test]
@@ -67,7 +354,7 @@ fn test_iterators(){
     tree.insert(1, 1321);
     tree.insert(3, 1322);
     let init_key = 0;
-    let mut iter = RangePairIter::<u64,i32>{tree: &tree, prev: Some(&init_key), from: Bound::Unbounded, to: Bound::Unbounded};
+    let mut iter = RangePairIter::new(&tree, Bound::Excluded(&init_key), Bound::Unbounded);
     assert_eq!(iter.next().expect("should have a few values").0, &1);
     assert_eq!(iter.next().expect("should have a few values").0, &3);
     assert_eq!(iter.next().expect("should have a few values").0, &10);
@@ -76,7 +363,7 @@ fn test_iterators(){
     assert_eq!(iter.next().expect("should have a few values").0, &18);
     assert!(iter.next().is_none());
 
-    let mut iter = RangePairIter::new(&tree, Bound::Unbounded, Bound::Unbounded);
+    let mut iter = RangePairIter::new::<u64>(&tree, Bound::Unbounded, Bound::Unbounded);
     assert_eq!(iter.next().expect("should have a few values").0, &1);
     assert_eq!(iter.next().expect("should have a few values").0, &3);
     assert_eq!(iter.next().expect("should have a few values").0, &10);
@@ -85,17 +372,134 @@ fn test_iterators(){
     assert_eq!(iter.next().expect("should have a few values").0, &18);
     assert!(iter.next().is_none());
 
-    let mut iter = RangePairIter::new(&tree, Bound::Included(3), Bound::Included(17));
+    let mut iter = RangePairIter::new(&tree, Bound::Included(&3), Bound::Included(&17));
     assert_eq!(iter.next().expect("should have a few values").0, &3);
     assert_eq!(iter.next().expect("should have a few values").0, &10);
     assert_eq!(iter.next().expect("should have a few values").0, &13);
     assert_eq!(iter.next().expect("should have a few values").0, &17);
     assert!(iter.next().is_none());
 
-    let mut iter = RangePairIter::new(&tree, Bound::Excluded(1), Bound::Excluded(18));
+    let mut iter = RangePairIter::new(&tree, Bound::Excluded(&1), Bound::Excluded(&18));
     assert_eq!(iter.next().expect("should have a few values").0, &3);
     assert_eq!(iter.next().expect("should have a few values").0, &10);
     assert_eq!(iter.next().expect("should have a few values").0, &13);
     assert_eq!(iter.next().expect("should have a few values").0, &17);
     assert!(iter.next().is_none());
 }
+
+#[test]
+fn test_iterator_stacks(){
+    let mut tree = tree::AVLTree::<u64,i32>::new();
+    for key in 0..1000 {
+        tree.insert(key, 0);
+    }
+    //an AVL tree with 1000 nodes is at most 14 levels high, neither end may hold more nodes than that
+    let mut iter = RangePairIter::new::<u64>(&tree, Bound::Unbounded, Bound::Unbounded);
+    for key in 0..500 {
+        assert!(iter.front.len() <= 14 && iter.back.len() <= 14);
+        assert_eq!(iter.next().map(|(k,_)| *k), Some(key));
+        assert_eq!(iter.next_back().map(|(k,_)| *k), Some(999-key));
+    }
+    assert!(iter.front.is_empty() && iter.back.is_empty());
+    assert!(iter.next().is_none());
+    assert!(iter.next_back().is_none());
+
+    let empty = RangePairIter::new(&tree, Bound::Excluded(&500), Bound::Included(&500));
+    assert!(empty.front.is_empty() && empty.back.is_empty());
+}
+
+#[test]
+fn test_mut_iterators(){
+    let mut tree = tree::AVLTree::<u64,i32>::new();
+    for key in 0..100 {
+        tree.insert(key, 0);
+    }
+    for (key, data) in RangePairIterMut::new::<u64>(&mut tree, Bound::Unbounded, Bound::Unbounded) {
+        *data = *key as i32;
+    }
+    assert!(tree.iter().all(|(k,d)| *d == *k as i32));
+
+    let mut iter = RangePairIterMut::new(&mut tree, Bound::Included(&3), Bound::Included(&17));
+    for key in 3..18 {
+        assert_eq!(iter.next().expect("should have a few values").0, &key);
+    }
+    assert!(iter.next().is_none());
+    assert!(iter.next().is_none());
+
+    let mut iter = RangePairIterMut::new(&mut tree, Bound::Excluded(&3), Bound::Excluded(&17));
+    for key in 4..17 {
+        assert_eq!(iter.next().expect("should have a few values").0, &key);
+    }
+    assert!(iter.next().is_none());
+
+    let mut iter = RangePairIterMut::new(&mut tree, Bound::Excluded(&98), Bound::Excluded(&200));
+    assert_eq!(iter.next().expect("should have a few values").0, &99);
+    assert!(iter.next().is_none());
+
+    let mut iter = RangePairIterMut::new(&mut tree, Bound::Excluded(&99), Bound::Unbounded);
+    assert!(iter.next().is_none());
+
+    let bounds = [Bound::Unbounded, Bound::Included(7), Bound::Excluded(7), Bound::Included(50), Bound::Excluded(50),
+                      Bound::Included(99), Bound::Excluded(99), Bound::Included(120)];
+    for lower in bounds.iter() {
+        for upper in bounds.iter() {
+            let expected : Vec<u64> = (0..100).filter(|k| (*lower, *upper).contains(k)).collect();
+            let found : Vec<u64> = RangePairIterMut::new(&mut tree, lower.as_ref(), upper.as_ref()).map(|(k,_)| *k).collect();
+            assert_eq!(found, expected);
+        }
+    }
+}
+
+#[test]
+fn test_into_iter(){
+    let mut tree = tree::AVLTree::<u64,String>::new();
+    for key in (0..100).rev() {
+        tree.insert(key, key.to_string());
+    }
+    let mut iter = IntoIter::new(tree.root.take(), 100);
+    assert_eq!(iter.size_hint(), (100, Some(100)));
+    for key in 0..100 {
+        assert_eq!(iter.next(), Some((key, key.to_string())));
+    }
+    assert_eq!(iter.size_hint(), (0, Some(0)));
+    assert!(iter.next().is_none());
+    assert!(IntoIter::<u64,String>::new(None, 0).next().is_none());
+}
+
+#[test]
+fn test_double_ended_iterators(){
+    let mut tree = tree::AVLTree::<u64,i32>::new();
+    for key in 0..20 {
+        tree.insert(key*2, 1337);
+    }
+    let all : Vec<u64> = RangePairIter::new::<u64>(&tree, Bound::Unbounded, Bound::Unbounded).rev().map(|(k,_)| *k).collect();
+    assert_eq!(all, (0..20).rev().map(|k| k*2).collect::<Vec<u64>>());
+
+    let bounds = [Bound::Unbounded, Bound::Included(7), Bound::Excluded(7), Bound::Included(8), Bound::Excluded(8),
+                      Bound::Included(30), Bound::Excluded(30), Bound::Included(31), Bound::Excluded(50)];
+    for lower in bounds.iter() {
+        for upper in bounds.iter() {
+            let expected : Vec<u64> = (0..20).map(|k| k*2).filter(|k| (*lower, *upper).contains(k)).collect();
+            let forward : Vec<u64> = RangePairIter::new(&tree, lower.as_ref(), upper.as_ref()).map(|(k,_)| *k).collect();
+            assert_eq!(forward, expected);
+            let reversed : Vec<u64> = RangePairIter::new(&tree, lower.as_ref(), upper.as_ref()).rev().map(|(k,_)| *k).collect();
+            assert_eq!(reversed, expected.iter().rev().cloned().collect::<Vec<u64>>());
+            //alternate between both ends, they have to meet in the middle without overlapping
+            let mut iter = RangePairIter::new(&tree, lower.as_ref(), upper.as_ref());
+            let mut front = vec![];
+            let mut back = vec![];
+            while let Some((k,_)) = iter.next() {
+                front.push(*k);
+                match iter.next_back() {
+                    Some((k,_)) => back.push(*k),
+                    None => break
+                }
+            }
+            assert!(iter.next().is_none());
+            assert!(iter.next_back().is_none());
+            back.reverse();
+            front.extend(back);
+            assert_eq!(front, expected);
+        }
+    }
+}
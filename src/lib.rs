@@ -1,11 +1,27 @@
 //#![crate_id = "avl_tree"]
 #![crate_type = "lib"]
-#![feature(test)]
-#![feature(collections_bound)]
-#![feature(rand)]
 
+#[cfg(test)]
+extern crate rand;
 
 mod node;
 pub mod tree;
 mod iterators;
+mod entry;
+mod error;
+mod cursor;
+mod finger;
+mod arena;
+mod arena_entry;
+mod arena_cursor;
+mod balance;
 pub use tree::AVLTree;
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+pub use error::{UnsortedError, PushBackError, CursorInsertError};
+pub use cursor::{Cursor, CursorMut};
+pub use finger::Finger;
+pub use iterators::{RangePairIter, RangePairIterMut, ValuesMut, IntoIter, IntoKeys, IntoValues, ExtractIf};
+pub use arena::{ArenaAVLTree, ArenaIter, ArenaIterMut, ArenaIntoIter, ArenaDrain, ArenaExtractIf};
+pub use arena_entry::{ArenaEntry, ArenaOccupiedEntry, ArenaVacantEntry};
+pub use arena_cursor::{ArenaCursor, ArenaCursorMut};
+pub use balance::{BalancePolicy, Avl, Wavl, WeightBalanced};
@@ -1,26 +1,99 @@
-extern crate rand;
-extern crate test;
+use node::{Node, Link, SearchPath, remove_at};
+use node::{insert,delete,remove,search,search_mut,min_pair,max_pair};
+#[cfg(test)]
+use node::is_balanced;
+use node::{min_from, min_after, max_upto, max_before, remove_min, remove_max, from_sorted};
+use node::{rank, split, split_at_bound, concat, concat_optional, push_back};
+use node::{union, intersection, difference, symmetric_difference};
+use iterators::{RangePairIter, RangePairIterMut, ValuesMut, IntoIter, IntoKeys, IntoValues, ExtractIf};
+use entry::{self, Entry, OccupiedEntry};
+use cursor::{self, Cursor, CursorMut};
+use finger::{self, Finger};
+use error::{UnsortedError, PushBackError};
+use balance::{BalancePolicy, Avl};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::ops::{Bound, Index, RangeBounds};
 
-use node::Node;
-use node::{insert,delete,search,min,max,is_avl_tree, to_string, min_pair, max_pair};
-use iterators::RangePairIter;
-use std::collections::Bound;
 
-
-pub struct AVLTree<K:Ord+Copy,D> {
-    pub root: Option<Box<Node<K,D>>>
+#[derive(Clone)]
+pub struct AVLTree<K:Ord, D, P:BalancePolicy=Avl> {
+    pub root: Option<Box<Node<K,D,P>>>,
+    pub(crate) len: usize,
+    policy: PhantomData<P>,
 }
 
-impl <K:Ord+Copy,D> AVLTree<K,D>{
+impl <K:Ord,D> AVLTree<K,D>{
 
 /// This function will construct a new empty AVLTree.
 /// # Examples
 /// ```
-/// extern crate avl_tree;
 /// let mut t=avl_tree::AVLTree::<u64,i32>::new();
 /// ```
     pub fn new() -> AVLTree<K,D>{
-        AVLTree{root: None}
+        AVLTree::with_policy(Avl)
+    }
+}
+
+impl <K:Ord,D,P:BalancePolicy> AVLTree<K,D,P>{
+
+/// This function will construct a new empty tree that is balanced by the given policy. `new` builds
+/// trees with the default policy `Avl`, the policy can also be chosen by the type of the tree.
+/// # Examples
+/// ```
+/// use avl_tree::{AVLTree, Wavl, WeightBalanced};
+/// let mut t = AVLTree::with_policy(Wavl);
+/// t.insert(2,25);
+/// assert_eq!(t.get(&2), Some(&25));
+///
+/// let t : AVLTree<u64,i32,WeightBalanced> = (0..100).map(|k| (k, 0)).collect();
+/// assert_eq!(t.len(), 100);
+/// ```
+    pub fn with_policy(_policy: P) -> AVLTree<K,D,P>{
+        AVLTree{root: None, len: 0, policy: PhantomData}
+    }
+
+/// This function will build a tree from pairs that are sorted by strictly ascending keys. The tree
+/// is built bottom up in linear time, instead of inserting and rebalancing every single pair. If a
+/// key is not bigger than the key before it, an error with its position is returned.
+/// # Examples
+/// ```
+/// let t : avl_tree::AVLTree<u64,u64> = avl_tree::AVLTree::from_sorted_iter((0..100).map(|k| (k, k*2))).unwrap();
+/// assert_eq!(t.len(), 100);
+/// assert_eq!(t.get(&21), Some(&42));
+///
+/// let unsorted = avl_tree::AVLTree::<_,_,avl_tree::Wavl>::from_sorted_iter(vec![(1,'a'), (3,'b'), (2,'c')]);
+/// assert_eq!(unsorted.unwrap_err().position(), 2);
+/// ```
+    pub fn from_sorted_iter<I: IntoIterator<Item=(K,D)>>(pairs: I) -> Result<AVLTree<K,D,P>, UnsortedError> {
+        let mut sorted : Vec<(K,D)> = Vec::new();
+        for (key, data) in pairs {
+            if sorted.last().is_some_and(|last| last.0 >= key) {
+                return Err(UnsortedError::new(sorted.len()))
+            }
+            sorted.push((key, data));
+        }
+        Ok(AVLTree::from_sorted_iter_unchecked(sorted))
+    }
+
+/// This function will build a tree from pairs that are sorted by strictly ascending keys, without
+/// checking the order of the keys. The tree is built bottom up in linear time. If the keys are not
+/// strictly ascending, the resulting tree is broken and lookups will miss keys.
+/// # Examples
+/// ```
+/// let t : avl_tree::AVLTree<u64,u64> = avl_tree::AVLTree::from_sorted_iter_unchecked((0..100).map(|k| (k, k*2)));
+/// assert_eq!(t.len(), 100);
+/// assert_eq!(t.last_key_value(), Some((&99,&198)));
+/// ```
+    pub fn from_sorted_iter_unchecked<I: IntoIterator<Item=(K,D)>>(pairs: I) -> AVLTree<K,D,P> {
+        let sorted : Vec<(K,D)> = pairs.into_iter().collect();
+        let len = sorted.len();
+        AVLTree::from_root(from_sorted::<P,_,_,_>(&mut sorted.into_iter(), len), len)
     }
 
 /// This function will insert the key,value pair into the tree, overwriting the old data if the key is allready
@@ -29,33 +102,116 @@ impl <K:Ord+Copy,D> AVLTree<K,D>{
 /// ```
 /// let mut t=avl_tree::AVLTree::<u64,i32>::new();
 /// t.insert(2,25);
-/// assert_eq!(t.get(2), Some(&25));
+/// assert_eq!(t.get(&2), Some(&25));
 /// t.insert(2,30);
-/// assert_eq!(t.get(2), Some(&30));
+/// assert_eq!(t.get(&2), Some(&30));
 /// ```
     pub fn insert(&mut self, key: K, data: D) {
-        match self.root.take() {
-            Some(box_to_node) => self.root = Some(insert::<K,D>(key, data, box_to_node)),
-            None => self.root = Some(Box::new(Node::new(key,data))),
-        }
+        if insert::<P,_,_>(key, data, &mut self.root).is_none() { self.len += 1 }
+    }
+
+/// This function will insert the key,value pair as new maximum of the tree. The right spine is
+/// walked down without comparing keys, only the current maximum is compared with the key, and the
+/// rebalancing stops at the deepest node of the spine that is not balanced. So every pair takes one
+/// comparison and amortized O(1) rotations, but walking down the spine still takes O(log n). To
+/// append many ascending keys like timestamps or sequence numbers in amortized O(1) each, use
+/// `push_back` of a `finger`, which stays at the end of the spine. If the key is not bigger than all
+/// keys of the tree, the pair is handed back in an error.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// for key in 0..10 {
+///     t.push_back(key, 0).unwrap();
+/// }
+/// assert_eq!(t.len(), 10);
+/// let error = t.push_back(5, 1).unwrap_err();
+/// assert_eq!(error.into_pair(), (5,1));
+/// assert_eq!(t.get(&5), Some(&0));
+/// ```
+    pub fn push_back(&mut self, key: K, data: D) -> Result<(), PushBackError<K,D>> {
+        push_back::<P,_,_>(key, data, &mut self.root).map_err(|(key, data)| PushBackError::new(key, data))?;
+        self.len += 1;
+        Ok(())
+    }
+
+/// This function will return a finger into the tree, which inserts pairs starting from where it
+/// inserted the last pair instead of from the root. Appending ascending keys with its `push_back`
+/// takes amortized O(1) under the policies `Avl` and `Wavl`, see `Finger`.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// {
+///     let mut finger = t.finger();
+///     for key in 0..100 {
+///         finger.push_back(key*2, 0).unwrap();
+///     }
+///     assert_eq!(finger.insert(51, 1), None);
+/// }
+/// assert_eq!(t.len(), 101);
+/// assert_eq!(t.get(&51), Some(&1));
+/// ```
+    pub fn finger<'a>(&'a mut self) -> Finger<'a,K,D,P> {
+        finger::finger(self)
     }
 
 /// This function will remove the key,value pair from the tree, doing nothing if the key is not
-/// part of the tree.
+/// part of the tree. It returns true if the key was part of the tree, false otherwise.
 /// # Examples
 /// ```
 /// let mut t=avl_tree::AVLTree::<u64,i32>::new();
 /// t.insert(2,25);
-/// t.delete(2);
+/// assert!(t.delete(&2));
 /// assert!(t.empty());
-/// t.delete(3);
+/// assert!(!t.delete(&3));
 /// assert!(t.empty());
 /// ```
-    pub fn delete(&mut self, key: K){
-        match self.root.take() {
-            Some(box_to_node) => self.root = delete(key,box_to_node),
-            None => return
-        }
+    pub fn delete<Q:Ord+?Sized>(&mut self, key: &Q) -> bool where K: Borrow<Q> {
+        let deleted = delete::<P,_,_,_>(key, &mut self.root);
+        if deleted { self.len -= 1 }
+        deleted
+    }
+
+/// This function will remove the key,value pair from the tree and return Some(data) of the removed
+/// pair, or None if the key is not part of the tree.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,String>::new();
+/// t.insert(2,"two".to_string());
+/// assert_eq!(t.remove(&2), Some("two".to_string()));
+/// assert_eq!(t.remove(&2), None);
+/// assert!(t.empty());
+/// ```
+    pub fn remove<Q:Ord+?Sized>(&mut self, key: &Q) -> Option<D> where K: Borrow<Q> {
+        self.remove_entry(key).map(|(_,data)| data)
+    }
+
+/// This function will remove the key,value pair from the tree and return Some((key,data)) of the
+/// removed pair, or None if the key is not part of the tree.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// assert_eq!(t.remove_entry(&2), Some((2,25)));
+/// assert_eq!(t.remove_entry(&2), None);
+/// ```
+    pub fn remove_entry<Q:Ord+?Sized>(&mut self, key: &Q) -> Option<(K,D)> where K: Borrow<Q> {
+        let removed = remove::<P,_,_,_>(key, &mut self.root);
+        if removed.is_some() { self.len -= 1 }
+        removed
+    }
+
+/// This function will return the entry for the given key, which can be used to inspect, insert,
+/// modify or remove the data stored under it.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.entry(2).or_insert(25);
+/// assert_eq!(t.get(&2), Some(&25));
+/// *t.entry(2).or_insert(0) += 5;
+/// assert_eq!(t.get(&2), Some(&30));
+/// ```
+    pub fn entry<'a>(&'a mut self, key: K) -> Entry<'a,K,D,P> {
+        entry::entry(self, key)
     }
 
 /// This function will return the Some(data) stored under the given key or None if the key is not
@@ -64,13 +220,31 @@ impl <K:Ord+Copy,D> AVLTree<K,D>{
 /// ```
 /// let mut t=avl_tree::AVLTree::<u64,i32>::new();
 /// t.insert(2,25);
-/// assert_eq!(t.get(2), Some(&25));
-/// assert_eq!(t.get(3), None);
+/// assert_eq!(t.get(&2), Some(&25));
+/// assert_eq!(t.get(&3), None);
+///
+/// ```
+    pub fn get<Q:Ord+?Sized>(&self, key: &Q) -> Option<&D> where K: Borrow<Q> {
+        match self.root {
+            Some(ref box_to_node) =>search(key, box_to_node),
+            None => None
+        }
+    }
+
+/// This function will return Some(data) as a mutable reference to the data stored under the given
+/// key or None if the key is not known.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// *t.get_mut(&2).unwrap() += 5;
+/// assert_eq!(t.get(&2), Some(&30));
+/// assert_eq!(t.get_mut(&3), None);
 ///
 /// ```
-    pub fn get(&self, key: K) -> Option<&D>{
+    pub fn get_mut<Q:Ord+?Sized>(&mut self, key: &Q) -> Option<&mut D> where K: Borrow<Q> {
         match self.root {
-            Some(ref box_to_node) =>search(&key, box_to_node),
+            Some(ref mut box_to_node) => search_mut(key, box_to_node),
             None => None
         }
     }
@@ -81,11 +255,11 @@ impl <K:Ord+Copy,D> AVLTree<K,D>{
 /// ```
 /// let mut t=avl_tree::AVLTree::<u64,i32>::new();
 /// t.insert(2,25);
-/// assert_eq!(t.get_or(2,&2000), &25);
-/// assert_eq!(t.get_or(3,&2000), &2000);
+/// assert_eq!(t.get_or(&2,&2000), &25);
+/// assert_eq!(t.get_or(&3,&2000), &2000);
 ///
 /// ```
-    pub fn get_or<'a>(&'a self, key: K, default: &'a D) -> &D{
+    pub fn get_or<'a, Q:Ord+?Sized>(&'a self, key: &Q, default: &'a D) -> &'a D where K: Borrow<Q> {
         self.get(key).map_or(default, |data| data)
     }
 
@@ -94,11 +268,11 @@ impl <K:Ord+Copy,D> AVLTree<K,D>{
 /// ```
 /// let mut t=avl_tree::AVLTree::<u64,i32>::new();
 /// t.insert(2,25);
-/// assert!(!t.contains(3));
-/// assert!(t.contains(2));
+/// assert!(!t.contains(&3));
+/// assert!(t.contains(&2));
 ///
 /// ```
-    pub fn contains(&self, key: K) -> bool {
+    pub fn contains<Q:Ord+?Sized>(&self, key: &Q) -> bool where K: Borrow<Q> {
         self.get(key).is_some()
     }
 
@@ -113,18 +287,45 @@ impl <K:Ord+Copy,D> AVLTree<K,D>{
 /// ```
     pub fn empty(&self) -> bool { self.root.is_none() }
 
+/// This function will return true if the tree is empty, false otherwise. It is the same as `empty`,
+/// under the name used by the standard collections.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// assert!(t.is_empty());
+/// t.insert(2,25);
+/// assert!(!t.is_empty());
+///
+/// ```
+    pub fn is_empty(&self) -> bool { self.empty() }
+
+/// This function will return the number of key,value pairs stored in the tree.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// assert_eq!(t.len(), 0);
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// t.insert(2,30);
+/// assert_eq!(t.len(), 2);
+/// t.delete(&2);
+/// assert_eq!(t.len(), 1);
+///
+/// ```
+    pub fn len(&self) -> usize { self.len }
+
 /// This function will return the key/value pair with the smallest key in the tree, or None if the
-/// tree is empty.
+/// tree is empty. Like in `BTreeMap` it is not called `min`, which `Ord::min` would shadow.
 /// # Examples
 /// ```
 /// let mut t=avl_tree::AVLTree::<u64,i32>::new();
 /// t.insert(2,25);
 /// t.insert(3,50);
-/// assert_eq!(t.min().unwrap().0, &2);
-/// assert_eq!(t.min().unwrap().1, &25);
+/// assert_eq!(t.first_key_value().unwrap().0, &2);
+/// assert_eq!(t.first_key_value().unwrap().1, &25);
 ///
 /// ```
-    pub fn min<'a>(&'a self) -> Option<(&'a K,&'a D)> {
+    pub fn first_key_value(&self) -> Option<(&K,&D)> {
         match self.root {
             Some(ref root) => Some(min_pair(root)),
             None => None
@@ -132,23 +333,237 @@ impl <K:Ord+Copy,D> AVLTree<K,D>{
     }
 
 /// This function will return the key/value pair with the biggest key in the tree, or None if the
-/// tree is empty.
+/// tree is empty. Like in `BTreeMap` it is not called `max`, which `Ord::max` would shadow.
 /// # Examples
 /// ```
 /// let mut t=avl_tree::AVLTree::<u64,i32>::new();
 /// t.insert(2,25);
 /// t.insert(3,50);
-/// assert_eq!(t.max().unwrap().0, &3);
-/// assert_eq!(t.max().unwrap().1, &50);
+/// assert_eq!(t.last_key_value().unwrap().0, &3);
+/// assert_eq!(t.last_key_value().unwrap().1, &50);
 ///
 /// ```
-    pub fn max<'a>(&'a self) -> Option<(&'a K,&'a D)> {
+    pub fn last_key_value(&self) -> Option<(&K,&D)> {
         match self.root {
             Some(ref root) => Some(max_pair(root)),
             None => None
         }
     }
 
+/// This function will remove the key/value pair with the smallest key from the tree and return it,
+/// or None if the tree is empty.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// assert_eq!(t.pop_min(), Some((2,25)));
+/// assert_eq!(t.pop_min(), Some((3,50)));
+/// assert_eq!(t.pop_min(), None);
+///
+/// ```
+    pub fn pop_min(&mut self) -> Option<(K,D)> {
+        let min = remove_min::<P,_,_>(&mut self.root);
+        if min.is_some() { self.len -= 1 }
+        min
+    }
+
+/// This function will remove the key/value pair with the biggest key from the tree and return it,
+/// or None if the tree is empty.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// assert_eq!(t.pop_max(), Some((3,50)));
+/// assert_eq!(t.pop_max(), Some((2,25)));
+/// assert_eq!(t.pop_max(), None);
+///
+/// ```
+    pub fn pop_max(&mut self) -> Option<(K,D)> {
+        let max = remove_max::<P,_,_>(&mut self.root);
+        if max.is_some() { self.len -= 1 }
+        max
+    }
+
+/// This function will return the entry of the smallest key in the tree, which can be used to modify
+/// or remove it in place, or None if the tree is empty.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// *t.first_entry().unwrap().get_mut() += 1;
+/// assert_eq!(t.get(&2), Some(&26));
+/// assert_eq!(t.first_entry().unwrap().remove(), 26);
+/// assert_eq!(t.first_key_value(), Some((&3,&50)));
+///
+/// ```
+    pub fn first_entry<'a>(&'a mut self) -> Option<OccupiedEntry<'a,K,D,P>> {
+        entry::first_entry(self)
+    }
+
+/// This function will return the entry of the biggest key in the tree, which can be used to modify
+/// or remove it in place, or None if the tree is empty.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// assert_eq!(t.last_entry().unwrap().key(), &3);
+/// assert_eq!(t.last_entry().unwrap().insert(60), 50);
+/// assert_eq!(t.get(&3), Some(&60));
+///
+/// ```
+    pub fn last_entry<'a>(&'a mut self) -> Option<OccupiedEntry<'a,K,D,P>> {
+        entry::last_entry(self)
+    }
+
+/// This function will return a cursor pointing at the pair with the smallest key above the given
+/// bound, or at the ghost position between the maximal and the minimal pair if there is no such key.
+/// The cursor can move back and forth from there.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::AVLTree<u64,i32> = (0..10).map(|k| (k*2, 0)).collect();
+/// let mut cursor = t.lower_bound_cursor(Bound::Excluded(&4));
+/// assert_eq!(cursor.key(), Some(&6));
+/// cursor.move_prev();
+/// assert_eq!(cursor.key(), Some(&4));
+/// assert_eq!(t.lower_bound_cursor(Bound::Unbounded).key(), Some(&0));
+/// assert_eq!(t.lower_bound_cursor(Bound::Included(&19)).key(), None);
+/// ```
+    pub fn lower_bound_cursor<'a, Q:Ord+?Sized>(&'a self, bound: Bound<&Q>) -> Cursor<'a,K,D,P> where K: Borrow<Q> {
+        cursor::lower_bound_cursor(self, bound)
+    }
+
+/// This function will return a cursor pointing at the pair with the biggest key below the given
+/// bound, or at the ghost position between the maximal and the minimal pair if there is no such key.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let t : avl_tree::AVLTree<u64,i32> = (0..10).map(|k| (k*2, 0)).collect();
+/// assert_eq!(t.upper_bound_cursor(Bound::Excluded(&4)).key(), Some(&2));
+/// assert_eq!(t.upper_bound_cursor(Bound::Included(&4)).key(), Some(&4));
+/// assert_eq!(t.upper_bound_cursor(Bound::Unbounded).key(), Some(&18));
+/// assert_eq!(t.upper_bound_cursor(Bound::Excluded(&0)).key(), None);
+/// ```
+    pub fn upper_bound_cursor<'a, Q:Ord+?Sized>(&'a self, bound: Bound<&Q>) -> Cursor<'a,K,D,P> where K: Borrow<Q> {
+        cursor::upper_bound_cursor(self, bound)
+    }
+
+/// This function will return a cursor like `lower_bound_cursor`, which can also modify values,
+/// remove pairs and insert new pairs next to its position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t : avl_tree::AVLTree<u64,i32> = (0..10).map(|k| (k*2, 0)).collect();
+/// {
+///     let mut cursor = t.lower_bound_cursor_mut(Bound::Excluded(&4));
+///     cursor.insert_before(5, 1).unwrap();
+///     *cursor.value_mut().unwrap() = 2;
+///     cursor.move_next();
+///     assert_eq!(cursor.remove_current(), Some((8,0)));
+/// }
+/// assert_eq!(t.range(4..=10).map(|(k,v)| (*k,*v)).collect::<Vec<_>>(), vec![(4,0), (5,1), (6,2), (10,0)]);
+/// ```
+    pub fn lower_bound_cursor_mut<'a, Q:Ord+?Sized>(&'a mut self, bound: Bound<&Q>) -> CursorMut<'a,K,D,P> where K: Borrow<Q> {
+        cursor::lower_bound_cursor_mut(self, bound)
+    }
+
+/// This function will return a cursor like `upper_bound_cursor`, which can also modify values,
+/// remove pairs and insert new pairs next to its position.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t : avl_tree::AVLTree<u64,i32> = (0..10).map(|k| (k*2, 0)).collect();
+/// {
+///     let mut cursor = t.upper_bound_cursor_mut(Bound::Unbounded);
+///     assert_eq!(cursor.remove_current(), Some((18,0)));
+///     assert_eq!(cursor.key(), None);
+///     assert!(cursor.insert_after(1, 1).is_err());
+///     cursor.insert_before(17, 1).unwrap();
+/// }
+/// assert_eq!(t.last_key_value(), Some((&17,&1)));
+/// ```
+    pub fn upper_bound_cursor_mut<'a, Q:Ord+?Sized>(&'a mut self, bound: Bound<&Q>) -> CursorMut<'a,K,D,P> where K: Borrow<Q> {
+        cursor::upper_bound_cursor_mut(self, bound)
+    }
+
+/// This function will return the key/value pair with the biggest key that is smaller than or equal
+/// to the given key, or None if there is no such key.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(4,50);
+/// assert_eq!(t.floor(&3), Some((&2,&25)));
+/// assert_eq!(t.floor(&4), Some((&4,&50)));
+/// assert_eq!(t.floor(&1), None);
+///
+/// ```
+    pub fn floor<Q:Ord+?Sized>(&self, key: &Q) -> Option<(&K,&D)> where K: Borrow<Q> {
+        match self.root {
+            Some(ref root) => max_upto(key, root),
+            None => None
+        }
+    }
+
+/// This function will return the key/value pair with the smallest key that is bigger than or equal
+/// to the given key, or None if there is no such key.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(4,50);
+/// assert_eq!(t.ceiling(&3), Some((&4,&50)));
+/// assert_eq!(t.ceiling(&2), Some((&2,&25)));
+/// assert_eq!(t.ceiling(&5), None);
+///
+/// ```
+    pub fn ceiling<Q:Ord+?Sized>(&self, key: &Q) -> Option<(&K,&D)> where K: Borrow<Q> {
+        match self.root {
+            Some(ref root) => min_from(key, root),
+            None => None
+        }
+    }
+
+/// This function will return the key/value pair with the biggest key that is strictly smaller than
+/// the given key, or None if there is no such key.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(4,50);
+/// assert_eq!(t.lower(&4), Some((&2,&25)));
+/// assert_eq!(t.lower(&2), None);
+///
+/// ```
+    pub fn lower<Q:Ord+?Sized>(&self, key: &Q) -> Option<(&K,&D)> where K: Borrow<Q> {
+        match self.root {
+            Some(ref root) => max_before(key, root),
+            None => None
+        }
+    }
+
+/// This function will return the key/value pair with the smallest key that is strictly bigger than
+/// the given key, or None if there is no such key.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(4,50);
+/// assert_eq!(t.higher(&2), Some((&4,&50)));
+/// assert_eq!(t.higher(&4), None);
+///
+/// ```
+    pub fn higher<Q:Ord+?Sized>(&self, key: &Q) -> Option<(&K,&D)> where K: Borrow<Q> {
+        match self.root {
+            Some(ref root) => min_after(key, root),
+            None => None
+        }
+    }
+
 /// This function will return a read only iterator for all (key,value) pairs in the tree.
 /// # Examples
 /// ```
@@ -158,49 +573,710 @@ impl <K:Ord+Copy,D> AVLTree<K,D>{
 /// }
 ///
 /// ```
-    pub fn iter(&self) -> RangePairIter<K,D>{
-        RangePairIter::new(self, Bound::Unbounded, Bound::Unbounded)
+    pub fn iter<'a>(&'a self) -> RangePairIter<'a,K,D,P>{
+        RangePairIter::new::<K>(self, Bound::Unbounded, Bound::Unbounded)
     }
 
-/// This function will return a read only iterator for all (key,value) pairs between the two bounds (which can
-/// be inclusive, exclusive or unbounded).
+/// This function will return a read only iterator for all (key,value) pairs within the given range.
+/// Any range expression works, as well as a pair of bounds (which can be inclusive, exclusive or
+/// unbounded).
 /// # Examples
 /// ```
-/// #![feature(collections_bound)]
-/// # extern crate avl_tree;
-/// use std::collections::Bound;
-/// //[...]
+/// use std::ops::Bound;
 /// # let mut t=avl_tree::AVLTree::<u64,i32>::new();
-/// for (key,val) in t.range(Bound::Excluded(32), Bound::Excluded(38)) {
+/// for (key,val) in t.range(32..=38) {
+///     println!("{} -> {}",key,val)
+/// }
+/// for (key,val) in t.range((Bound::Excluded(32), Bound::Excluded(38))) {
 ///     println!("{} -> {}",key,val)
 /// }
 ///
 /// ```
-    pub fn range(&self, min: Bound<K>, max: Bound<K>) -> RangePairIter<K,D>{
-        RangePairIter::new(self, min, max)
+    pub fn range<'a, Q:Ord+?Sized, R:RangeBounds<Q>>(&'a self, range: R) -> RangePairIter<'a,K,D,P> where K: Borrow<Q> {
+        RangePairIter::new(self, range.start_bound(), range.end_bound())
+    }
+
+/// This function will return an iterator for all (key,value) pairs in the tree that allows to modify
+/// the values.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// for (key,val) in t.iter_mut() {
+///     *val += *key as i32;
+/// }
+/// assert_eq!(t.get(&2), Some(&27));
+/// assert_eq!(t.get(&3), Some(&53));
+///
+/// ```
+    pub fn iter_mut<'a>(&'a mut self) -> RangePairIterMut<'a,K,D,P>{
+        RangePairIterMut::new::<K>(self, Bound::Unbounded, Bound::Unbounded)
+    }
+
+/// This function will return an iterator for all (key,value) pairs within the given range that
+/// allows to modify the values.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// for (_,val) in t.range_mut(3..) {
+///     *val = 0;
+/// }
+/// assert_eq!(t.get(&2), Some(&25));
+/// assert_eq!(t.get(&3), Some(&0));
+///
+/// ```
+    pub fn range_mut<'a, Q:Ord+?Sized, R:RangeBounds<Q>>(&'a mut self, range: R) -> RangePairIterMut<'a,K,D,P> where K: Borrow<Q> {
+        RangePairIterMut::new(self, range.start_bound(), range.end_bound())
+    }
+
+/// This function will return an iterator over mutable references to all values in the tree,
+/// ordered by their keys.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.insert(3,50);
+/// for val in t.values_mut() {
+///     *val *= 2;
+/// }
+/// assert_eq!(t.get(&2), Some(&50));
+/// assert_eq!(t.get(&3), Some(&100));
+///
+/// ```
+    pub fn values_mut<'a>(&'a mut self) -> ValuesMut<'a,K,D,P>{
+        ValuesMut::new(self.iter_mut())
     }
 
+/// This function will consume the tree and return an iterator over its keys in order.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(3,50);
+/// t.insert(2,25);
+/// let keys : Vec<u64> = t.into_keys().collect();
+/// assert_eq!(keys, vec![2,3]);
+///
+/// ```
+    pub fn into_keys(self) -> IntoKeys<K,D,P>{
+        IntoKeys::new(self.into_iter())
+    }
+
+/// This function will consume the tree and return an iterator over its values, ordered by their
+/// keys.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(3,50);
+/// t.insert(2,25);
+/// let values : Vec<i32> = t.into_values().collect();
+/// assert_eq!(values, vec![25,50]);
+///
+/// ```
+    pub fn into_values(self) -> IntoValues<K,D,P>{
+        IntoValues::new(self.into_iter())
+    }
+
+/// This function will remove all (key,value) pairs from the tree and return an iterator that
+/// yields them in order. The tree is empty right after the call, even if the iterator is not
+/// consumed.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(3,50);
+/// t.insert(2,25);
+/// let pairs : Vec<(u64,i32)> = t.drain().collect();
+/// assert_eq!(pairs, vec![(2,25),(3,50)]);
+/// assert!(t.empty());
+///
+/// ```
+    pub fn drain(&mut self) -> IntoIter<K,D,P>{
+        let len = self.len;
+        self.len = 0;
+        IntoIter::new(self.root.take(), len)
+    }
+
+/// This function will remove all key,value pairs from the tree.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// t.clear();
+/// assert!(t.empty());
+/// assert_eq!(t.len(), 0);
+///
+/// ```
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+/// This function will keep only the key,value pairs for which `f` returns true. `f` is called once
+/// for every pair in ascending order of the keys and may modify the values. The remaining pairs are
+/// rebuilt into a balanced tree, so this takes linear time instead of one deletion per removed pair.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::AVLTree<u64,i32> = (0..10).map(|k| (k, k as i32)).collect();
+/// t.retain(|k, v| { *v *= 2; k % 2 == 0 });
+/// assert_eq!(t.len(), 5);
+/// assert_eq!(t.get(&4), Some(&8));
+/// assert!(!t.contains(&5));
+///
+/// ```
+    pub fn retain<F: FnMut(&K, &mut D) -> bool>(&mut self, mut f: F) {
+        self.extract_if::<K,_,_>(.., |key, data| !f(key, data)).for_each(drop);
+    }
+
+/// This function will return an iterator that removes the key,value pairs within the given range
+/// for which `pred` returns true and yields them in ascending order of the keys. `pred` is called
+/// once for every pair in the range the iterator gets to and may modify the values of the pairs that
+/// are kept. The range is split off the tree in O(log n) and the pairs are visited lazily. When the
+/// iterator is dropped, the kept pairs are rebuilt into a tree and joined back in, which takes time
+/// linear in the size of the range. Like `BTreeMap::extract_if` only the pairs the iterator returned
+/// are removed, dropping it early keeps the rest of the range. If `pred` panics, the tree keeps all
+/// pairs that were not extracted so far.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::AVLTree<u64,i32> = (0..10).map(|k| (k, k as i32)).collect();
+/// let odd : Vec<(u64,i32)> = t.extract_if(3..8, |k, _| k % 2 == 1).collect();
+/// assert_eq!(odd, vec![(3,3), (5,5), (7,7)]);
+/// assert_eq!(t.len(), 7);
+/// assert!(t.contains(&1));
+/// assert!(!t.contains(&5));
+///
+/// assert_eq!(t.extract_if(.., |_, _| true).next(), Some((0,0)));
+/// assert_eq!(t.len(), 6);
+/// ```
+    pub fn extract_if<'a, Q:Ord+?Sized, R:RangeBounds<Q>, F:FnMut(&K, &mut D) -> bool>(&'a mut self, range: R, pred: F) -> ExtractIf<'a,K,D,F,P> where K: Borrow<Q> {
+        let (before, in_range, after) = self.split_range(range.start_bound(), range.end_bound());
+        let len = P::count(&in_range);
+        self.len -= len;
+        ExtractIf::new(self, before, IntoIter::new(in_range, len), after, pred)
+    }
+
+/// This function will split the tree at `key`: the tree keeps all key,value pairs with keys smaller
+/// than `key` and all others, including the one stored under `key`, are returned as a new tree.
+/// The tree is cut along the search path of `key`, which takes O(log n). The nodes store no sizes
+/// under the rank balancing policies, so like `BTreeMap::split_off` the lower of both parts is
+/// counted, which takes time linear in its size.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::AVLTree<u64,i32> = (0..10).map(|k| (k, k as i32)).collect();
+/// let upper = t.split_off(&6);
+/// assert_eq!(t.len(), 6);
+/// assert_eq!(t.last_key_value(), Some((&5,&5)));
+/// assert_eq!(upper.len(), 4);
+/// assert_eq!(upper.first_key_value(), Some((&6,&6)));
+///
+/// ```
+    pub fn split_off<Q:Ord+?Sized>(&mut self, key: &Q) -> AVLTree<K,D,P> where K: Borrow<Q> {
+        let (smaller, bigger) = split_at_bound::<P,_,_,_>(Bound::Included(key), self.root.take());
+        let (smaller_len, bigger_len) = split_len::<P,_,_>(self.len, &smaller, &bigger);
+        self.root = smaller;
+        self.len = smaller_len;
+        AVLTree::from_root(bigger, bigger_len)
+    }
+
+/// This function will split the tree at `key` into a tree of all smaller keys, the key,value pair
+/// stored under `key` if there is one, and a tree of all bigger keys. This takes O(log n), plus the
+/// counting of the lower part like `split_off`.
+/// # Examples
+/// ```
+/// let t: avl_tree::AVLTree<u64,i32> = (0..10).map(|k| (k, k as i32)).collect();
+/// let (smaller, pair, bigger) = t.split(&6);
+/// assert_eq!(smaller.len(), 6);
+/// assert_eq!(pair, Some((6,6)));
+/// assert_eq!(bigger.len(), 3);
+///
+/// ```
+    #[allow(clippy::type_complexity)]
+    pub fn split<Q:Ord+?Sized>(self, key: &Q) -> (AVLTree<K,D,P>, Option<(K,D)>, AVLTree<K,D,P>) where K: Borrow<Q> {
+        let (smaller, pair, bigger) = split::<P,_,_,_>(key, self.root);
+        let len = if pair.is_some() { self.len - 1 } else { self.len };
+        let (smaller_len, bigger_len) = split_len::<P,_,_>(len, &smaller, &bigger);
+        (AVLTree::from_root(smaller, smaller_len), pair, AVLTree::from_root(bigger, bigger_len))
+    }
+
+/// This function will remove all key,value pairs with keys within the given bounds from the tree and
+/// return them as a new tree. The range is cut out with two splits and the remaining parts are
+/// joined again in O(log n), plus the counting of the removed pairs or the remaining pairs,
+/// whichever tree is lower, like `split_off`.
+/// # Examples
+/// ```
+/// use std::ops::Bound;
+/// let mut t: avl_tree::AVLTree<u64,i32> = (0..10).map(|k| (k, k as i32)).collect();
+/// let removed = t.delete_range(Bound::Included(&3), Bound::Excluded(&7));
+/// assert_eq!(removed.iter().map(|(k,_)| *k).collect::<Vec<u64>>(), vec![3,4,5,6]);
+/// assert_eq!(t.iter().map(|(k,_)| *k).collect::<Vec<u64>>(), vec![0,1,2,7,8,9]);
+///
+/// ```
+    pub fn delete_range<Q:Ord+?Sized>(&mut self, lower: Bound<&Q>, upper: Bound<&Q>) -> AVLTree<K,D,P> where K: Borrow<Q> {
+        let (before, range, after) = self.split_range(lower, upper);
+        self.root = concat_optional::<P,_,_>(before, after);
+        let (len, range_len) = split_len::<P,_,_>(self.len, &self.root, &range);
+        self.len = len;
+        AVLTree::from_root(range, range_len)
+    }
+
+/// This function will move all key,value pairs from `other` into the tree, leaving `other` empty.
+/// If a key is part of both trees, the data from `other` replaces the data in this tree. If all keys
+/// of one tree are smaller than all keys of the other tree, both trees are joined in O(log n).
+/// Otherwise the pairs of both trees are merged and rebuilt into a balanced tree in linear time.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::AVLTree<u64,i32> = (0..5).map(|k| (k, 0)).collect();
+/// let mut other: avl_tree::AVLTree<u64,i32> = (5..10).map(|k| (k, 1)).collect();
+/// t.append(&mut other);
+/// assert_eq!(t.len(), 10);
+/// assert!(other.empty());
+///
+/// let mut overlapping: avl_tree::AVLTree<u64,i32> = (8..12).map(|k| (k, 2)).collect();
+/// t.append(&mut overlapping);
+/// assert_eq!(t.len(), 12);
+/// assert_eq!(t.get(&9), Some(&2));
+///
+/// ```
+    pub fn append(&mut self, other: &mut AVLTree<K,D,P>) {
+        let (left_len, right_len) = (self.len, other.len);
+        other.len = 0;
+        self.len = left_len + right_len;
+        let (left, right) = match (self.root.take(), other.root.take()) {
+            (Some(left), Some(right)) => (left, right),
+            (None, root) | (root, None) => {
+                self.root = root;
+                return
+            }
+        };
+        if max_pair(&left).0 < min_pair(&right).0 {
+            self.root = Some(concat::<P,_,_>(left, right));
+        } else if max_pair(&right).0 < min_pair(&left).0 {
+            self.root = Some(concat::<P,_,_>(right, left));
+        } else {
+            let merged = merge_sorted(IntoIter::new(Some(left), left_len), IntoIter::new(Some(right), right_len));
+            let len = merged.len();
+            self.root = from_sorted::<P,_,_,_>(&mut merged.into_iter(), len);
+            self.len = len;
+        }
+    }
+
+/// This function will insert all key,value pairs of `batch` into the tree, as if they were inserted
+/// one after another: if a key appears more than once, the data that comes last wins. The batch is
+/// sorted, turned into a tree and united with this tree in one pass, which takes O(m log(n/m + 1))
+/// plus the sorting for a batch of size m, instead of m independent insertions.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::AVLTree<u64,i32> = (0..5).map(|k| (k, 0)).collect();
+/// t.insert_batch(vec![(9,1), (3,1), (9,2)]);
+/// assert_eq!(t.len(), 6);
+/// assert_eq!(t.get(&3), Some(&1));
+/// assert_eq!(t.get(&9), Some(&2));
+///
+/// ```
+    pub fn insert_batch(&mut self, batch: Vec<(K,D)>) {
+        self.insert_batch_with(batch, |_, _, later| later)
+    }
+
+/// This function will insert all key,value pairs of `batch` into the tree like `insert_batch`, but if
+/// a key is part of the tree or appears more than once in the batch, its data is combined by `f`.
+/// `f` gets the key, the earlier data and the later data, where data in the tree comes before data
+/// in the batch and the batch is read from front to back. `|_, earlier, _| earlier` lets the first
+/// data win, so pairs whose keys are already part of the tree are dropped.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::AVLTree<u64,i32> = (0..5).map(|k| (k, 0)).collect();
+/// t.insert_batch_with(vec![(9,1), (3,1), (9,2)], |_, earlier, later| earlier + later);
+/// assert_eq!(t.get(&3), Some(&1));
+/// assert_eq!(t.get(&9), Some(&3));
+///
+/// t.insert_batch_with(vec![(3,5), (10,5)], |_, earlier, _| earlier);
+/// assert_eq!(t.get(&3), Some(&1));
+/// assert_eq!(t.get(&10), Some(&5));
+///
+/// ```
+    pub fn insert_batch_with<F: FnMut(&K, D, D) -> D>(&mut self, mut batch: Vec<(K,D)>, mut f: F) {
+        batch.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut sorted : Vec<(K,D)> = Vec::with_capacity(batch.len());
+        for (key, data) in batch {
+            if sorted.last().is_some_and(|last| last.0 == key) {
+                let (last_key, last_data) = sorted.pop().expect("checked above");
+                let data = f(&last_key, last_data, data);
+                sorted.push((last_key, data));
+            } else {
+                sorted.push((key, data));
+            }
+        }
+        let len = sorted.len();
+        let batch_root = from_sorted::<P,_,_,_>(&mut sorted.into_iter(), len);
+        let mut common = 0;
+        self.root = union::<P,_,_,_>(self.root.take(), batch_root, &mut |key, earlier, later| {
+            common += 1;
+            f(key, earlier, later)
+        });
+        self.len += len - common;
+    }
+
+/// This function will add all key,value pairs of `other` to the tree. If a key is part of both trees,
+/// its data is combined by `f`, which gets the key, the data from this tree and the data from `other`.
+/// Both trees are split and joined recursively, so adding a tree of size m to a tree of size n
+/// takes O(m log(n/m + 1)).
+/// # Examples
+/// ```
+/// let mut t: avl_tree::AVLTree<u64,i32> = (0..5).map(|k| (k, 1)).collect();
+/// let other: avl_tree::AVLTree<u64,i32> = (3..8).map(|k| (k, 10)).collect();
+/// t.union_with(other, |_, a, b| a + b);
+/// assert_eq!(t.len(), 8);
+/// assert_eq!(t.get(&2), Some(&1));
+/// assert_eq!(t.get(&3), Some(&11));
+/// assert_eq!(t.get(&7), Some(&10));
+///
+/// ```
+    pub fn union_with<F: FnMut(&K, D, D) -> D>(&mut self, other: AVLTree<K,D,P>, mut f: F) {
+        let mut common = 0;
+        self.root = union::<P,_,_,_>(self.root.take(), other.root, &mut |key, data, other_data| {
+            common += 1;
+            f(key, data, other_data)
+        });
+        self.len += other.len - common;
+    }
+
+/// This function will keep only the keys that are part of both trees, combining their data by `f`,
+/// which gets the key, the data from this tree and the data from `other`. This takes
+/// O(m log(n/m + 1)) for trees of sizes m <= n.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::AVLTree<u64,i32> = (0..5).map(|k| (k, 1)).collect();
+/// let other: avl_tree::AVLTree<u64,i32> = (3..8).map(|k| (k, 10)).collect();
+/// t.intersection_with(other, |_, a, b| a + b);
+/// assert_eq!(t.len(), 2);
+/// assert_eq!(t.get(&3), Some(&11));
+/// assert_eq!(t.get(&4), Some(&11));
+///
+/// ```
+    pub fn intersection_with<F: FnMut(&K, D, D) -> D>(&mut self, other: AVLTree<K,D,P>, mut f: F) {
+        let mut common = 0;
+        self.root = intersection::<P,_,_,_>(self.root.take(), other.root, &mut |key, data, other_data| {
+            common += 1;
+            f(key, data, other_data)
+        });
+        self.len = common;
+    }
+
+/// This function will remove all keys from the tree that are part of `other`, whose data is
+/// ignored. This takes O(m log(n/m + 1)) for trees of sizes m <= n.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::AVLTree<u64,i32> = (0..5).map(|k| (k, 1)).collect();
+/// let other: avl_tree::AVLTree<u64,String> = (3..8).map(|k| (k, k.to_string())).collect();
+/// t.difference(&other);
+/// assert_eq!(t.len(), 3);
+/// assert_eq!(t.last_key_value(), Some((&2,&1)));
+///
+/// ```
+    pub fn difference<E>(&mut self, other: &AVLTree<K,E,P>) {
+        let (root, removed) = difference::<P,_,_,_>(self.root.take(), &other.root);
+        self.root = root;
+        self.len -= removed;
+    }
+
+/// This function will keep the keys that are part of exactly one of both trees, together with their
+/// data. This takes O(m log(n/m + 1)) for trees of sizes m <= n.
+/// # Examples
+/// ```
+/// let mut t: avl_tree::AVLTree<u64,i32> = (0..5).map(|k| (k, 1)).collect();
+/// let other: avl_tree::AVLTree<u64,i32> = (3..8).map(|k| (k, 10)).collect();
+/// t.symmetric_difference(other);
+/// assert_eq!(t.iter().map(|(k,_)| *k).collect::<Vec<u64>>(), vec![0,1,2,5,6,7]);
+///
+/// ```
+    pub fn symmetric_difference(&mut self, other: AVLTree<K,D,P>) {
+        let (root, common) = symmetric_difference::<P,_,_>(self.root.take(), other.root);
+        self.root = root;
+        self.len = self.len + other.len - 2 * common;
+    }
+
+    //inserts a new pair at the end of a search path of this tree and returns the search path of the
+    //new node, see entry::VacantEntry and cursor::CursorMut
+    pub(crate) fn insert_at(&mut self, key: K, data: D, search: SearchPath) -> (SearchPath, &mut D) {
+        self.len += 1;
+        P::insert_at(key, data, search, &mut self.root)
+    }
+
+    //removes the pair at the end of a search path of this tree, see entry::OccupiedEntry
+    pub(crate) fn remove_at(&mut self, search: SearchPath) -> (K,D) {
+        self.len -= 1;
+        remove_at::<P,_,_>(search, &mut self.root)
+    }
+
+    //takes the root and splits it into the trees of the keys below, within and above the bounds
+#[allow(clippy::type_complexity)]
+    fn split_range<Q:Ord+?Sized>(&mut self, lower: Bound<&Q>, upper: Bound<&Q>) -> (Link<K,D,P>, Link<K,D,P>, Link<K,D,P>) where K: Borrow<Q> {
+        let (before, rest) = split_at_bound::<P,_,_,_>(lower, self.root.take());
+        let (range, after) = match upper {
+            Bound::Included(key) => split_at_bound::<P,_,_,_>(Bound::Excluded(key), rest),
+            Bound::Excluded(key) => split_at_bound::<P,_,_,_>(Bound::Included(key), rest),
+            Bound::Unbounded => (rest, None)
+        };
+        (before, range, after)
+    }
+
+    //wraps a root of a tree with len nodes into a tree
+    fn from_root(root: Option<Box<Node<K,D,P>>>, len: usize) -> AVLTree<K,D,P> {
+        AVLTree{root, len, policy: PhantomData}
+    }
+
+    #[cfg(test)]
     fn test_avl_tree(&self) -> bool {
-        is_avl_tree(&self.root)
+        is_balanced::<P,_,_>(&self.root) && P::count(&self.root) == self.len
     }
 }
 
-#[test]
-fn test_fuzz(){
-    let mut t = AVLTree::<u64,i32>::new();
+//divides the len pairs of a tree that was cut into first and second between both parts. Only the
+//part of lower rank is counted, which is the smaller one up to a constant factor.
+fn split_len<P:BalancePolicy,K:Ord,D>(len: usize, first: &Link<K,D,P>, second: &Link<K,D,P>) -> (usize, usize) {
+    if rank::<P,_,_>(first) <= rank::<P,_,_>(second) {
+        let first_len = P::count(first);
+        (first_len, len - first_len)
+    } else {
+        let second_len = P::count(second);
+        (len - second_len, second_len)
+    }
+}
+
+//merges two iterators over pairs that are sorted by their keys, if a key is part of both iterators
+//only the pair from right is kept
+pub(crate) fn merge_sorted<K:Ord,D,I:Iterator<Item=(K,D)>>(left: I, right: I) -> Vec<(K,D)> {
+    let mut left = left.peekable();
+    let mut right = right.peekable();
+    let mut merged = Vec::new();
+    loop {
+        let order = match (left.peek(), right.peek()) {
+            (Some(l), Some(r)) => l.0.cmp(&r.0),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => return merged
+        };
+        match order {
+            Ordering::Less => merged.extend(left.next()),
+            Ordering::Greater => merged.extend(right.next()),
+            Ordering::Equal => {
+                left.next();
+                merged.extend(right.next());
+            }
+        }
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy> IntoIterator for AVLTree<K,D,P> {
+    type Item = (K,D);
+    type IntoIter = IntoIter<K,D,P>;
+
+/// This function will consume the tree and return an iterator over its (key,value) pairs in order.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(3,50);
+/// t.insert(2,25);
+/// for (key,val) in t {
+///     println!("{} -> {}",key,val)
+/// }
+///
+/// ```
+    fn into_iter(self) -> IntoIter<K,D,P> {
+        IntoIter::new(self.root, self.len)
+    }
+}
+
+impl<'a, K:Ord,D,P:BalancePolicy> IntoIterator for &'a AVLTree<K,D,P> {
+    type Item = (&'a K,&'a D);
+    type IntoIter = RangePairIter<'a,K,D,P>;
+
+    fn into_iter(self) -> RangePairIter<'a,K,D,P> {
+        self.iter()
+    }
+}
+
+impl<'a, K:Ord,D,P:BalancePolicy> IntoIterator for &'a mut AVLTree<K,D,P> {
+    type Item = (&'a K,&'a mut D);
+    type IntoIter = RangePairIterMut<'a,K,D,P>;
+
+    fn into_iter(self) -> RangePairIterMut<'a,K,D,P> {
+        self.iter_mut()
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy> Default for AVLTree<K,D,P> {
+    fn default() -> AVLTree<K,D,P> {
+        AVLTree::from_root(None, 0)
+    }
+}
+
+impl<K:Ord+fmt::Debug,D:fmt::Debug,P:BalancePolicy> fmt::Debug for AVLTree<K,D,P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// Two trees are compared by their (key,value) pairs in order, regardless of the shape of the trees.
+impl<K:Ord,D:PartialEq,P:BalancePolicy> PartialEq for AVLTree<K,D,P> {
+    fn eq(&self, other: &AVLTree<K,D,P>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<K:Ord,D:Eq,P:BalancePolicy> Eq for AVLTree<K,D,P> {}
+
+impl<K:Ord,D:PartialOrd,P:BalancePolicy> PartialOrd for AVLTree<K,D,P> {
+    fn partial_cmp(&self, other: &AVLTree<K,D,P>) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<K:Ord,D:Ord,P:BalancePolicy> Ord for AVLTree<K,D,P> {
+/// This function will compare both trees lexicographically by their pairs in the order of the keys,
+/// like `BTreeMap` does.
+/// # Examples
+/// ```
+/// let small : avl_tree::AVLTree<u64,i32> = (0..3).map(|k| (k, 0)).collect();
+/// let big : avl_tree::AVLTree<u64,i32> = (0..3).map(|k| (k, 1)).collect();
+/// assert!(small < big);
+/// assert_eq!(small.clone().max(big.clone()), big);
+/// let mut trees = vec![big.clone(), small.clone()];
+/// trees.sort();
+/// assert_eq!(trees, vec![small, big]);
+///
+/// ```
+    fn cmp(&self, other: &AVLTree<K,D,P>) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<K:Ord+Hash,D:Hash,P:BalancePolicy> Hash for AVLTree<K,D,P> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for pair in self.iter() {
+            pair.hash(state);
+        }
+    }
+}
+
+impl<K:Ord+Borrow<Q>,D,P:BalancePolicy,Q:Ord+?Sized> Index<&Q> for AVLTree<K,D,P> {
+    type Output = D;
+
+/// This function will return the data stored under the given key and panic if the key is not part
+/// of the tree.
+/// # Examples
+/// ```
+/// let mut t=avl_tree::AVLTree::<u64,i32>::new();
+/// t.insert(2,25);
+/// assert_eq!(t[&2], 25);
+///
+/// ```
+    fn index(&self, key: &Q) -> &D {
+        self.get(key).expect("key not found in AVLTree")
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy> FromIterator<(K,D)> for AVLTree<K,D,P> {
+    fn from_iter<I: IntoIterator<Item=(K,D)>>(iter: I) -> AVLTree<K,D,P> {
+        let mut tree = AVLTree::default();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy> Extend<(K,D)> for AVLTree<K,D,P> {
+    fn extend<I: IntoIterator<Item=(K,D)>>(&mut self, iter: I) {
+        for (key, data) in iter {
+            self.insert(key, data);
+        }
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy,const N: usize> From<[(K,D); N]> for AVLTree<K,D,P> {
+/// This function will construct a tree from the given (key,value) pairs. If a key occurs more than
+/// once, the last value wins.
+/// # Examples
+/// ```
+/// let t : avl_tree::AVLTree<u64,i32> = avl_tree::AVLTree::from([(3,50),(2,25),(3,60)]);
+/// assert_eq!(t.len(), 2);
+/// assert_eq!(t[&3], 60);
+///
+/// let t : avl_tree::AVLTree<u64,i32,avl_tree::WeightBalanced> = [(3,50),(2,25)].into();
+/// assert_eq!(t.len(), 2);
+///
+/// ```
+    fn from(pairs: [(K,D); N]) -> AVLTree<K,D,P> {
+        IntoIterator::into_iter(pairs).collect()
+    }
+}
+
+impl<K:Ord,D,P:BalancePolicy> From<BTreeMap<K,D>> for AVLTree<K,D,P> {
+    fn from(map: BTreeMap<K,D>) -> AVLTree<K,D,P> {
+        AVLTree::from_sorted_iter_unchecked(map)
+    }
+}
+
+#[cfg(test)]
+fn fuzz<P:BalancePolicy>(mut t: AVLTree<u64,i32,P>){
     for _ in 1..5000 {
-        let decision = rand::random::<bool>();
+        let decision = ::rand::random::<bool>();
         if  decision {
-            let to_insert = rand::random::<u64>()%500;
+            let to_insert = ::rand::random::<u64>()%500;
             t.insert(to_insert, 1337);
-            assert!(t.contains(to_insert));
+            assert!(t.contains(&to_insert));
             assert!(t.test_avl_tree());
         } else {
-            let to_delete = rand::random::<u64>()%500;
-            t.delete(to_delete);
-            assert!(!t.contains(to_delete));
+            let to_delete = ::rand::random::<u64>()%500;
+            let was_contained = t.contains(&to_delete);
+            assert_eq!(t.delete(&to_delete), was_contained);
+            assert!(!t.contains(&to_delete));
             assert!(t.test_avl_tree());
         };
+        assert_eq!(t.len(), t.iter().count());
     };
-    return;
+}
+
+//inserts random keys and appends ascending keys with one finger, the tree has to stay balanced
+//after every insertion
+#[cfg(test)]
+fn fuzz_finger<P:BalancePolicy>(mut t: AVLTree<u64,i32,P>){
+    let mut model = BTreeMap::new();
+    let mut last : u64 = 0;
+    for round in 0..1000 {
+        {
+            let mut finger = t.finger();
+            for _ in 0..::rand::random::<u8>() % 8 {
+                if ::rand::random::<bool>() {
+                    let key = last.saturating_sub(20) + ::rand::random::<u64>() % 40;
+                    assert_eq!(finger.insert(key, round), model.insert(key, round));
+                    last = key;
+                } else {
+                    let key = model.keys().next_back().map_or(0, |max| max + 1 + ::rand::random::<u64>() % 3);
+                    assert!(finger.push_back(key, round).is_ok());
+                    assert!(finger.push_back(key, round).is_err());
+                    model.insert(key, round);
+                    last = key;
+                }
+            }
+        }
+        assert!(t.test_avl_tree());
+        assert_eq!(t.len(), model.len());
+    }
+    assert!(t.iter().eq(model.iter()));
+}
+
+#[test]
+fn test_fuzz(){
+    fuzz(AVLTree::new());
+    fuzz(AVLTree::with_policy(::balance::Wavl));
+    fuzz(AVLTree::with_policy(::balance::WeightBalanced));
+}
+
+#[test]
+fn test_fuzz_finger(){
+    fuzz_finger(AVLTree::new());
+    fuzz_finger(AVLTree::with_policy(::balance::Wavl));
+    fuzz_finger(AVLTree::with_policy(::balance::WeightBalanced));
 }
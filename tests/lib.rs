@@ -1,9 +1,7 @@
-#![feature(collections_bound)]
-
 extern crate avl_tree;
 extern crate rand;
 
-use std::collections::Bound;
+use std::ops::Bound;
 use std::time::Instant;
 
 #[test]
@@ -13,11 +11,11 @@ fn test_getters(){
     t.insert(1, data);
     t.insert(2, data+1);
     t.insert(3, data+2);
-    assert!(t.get_or(1, &0) == &data);
-    assert!(t.get_or(2, &0) == &(data+1));
-    assert!(t.get_or(3, &0) == &(data+2));
-    assert!(t.get_or(4, &0) == &0);
-    assert!(t.get(4) == None);
+    assert!(t.get_or(&1, &0) == &data);
+    assert!(t.get_or(&2, &0) == &(data+1));
+    assert!(t.get_or(&3, &0) == &(data+2));
+    assert!(t.get_or(&4, &0) == &0);
+    assert!(t.get(&4).is_none());
 }
 
 #[test]
@@ -27,11 +25,11 @@ fn test_contains(){
     t.insert(1, data);
     t.insert(2, data+1);
     t.insert(3, data+2);
-    assert!(!t.contains(0));
-    assert!(t.contains(1));
-    assert!(t.contains(2));
-    assert!(t.contains(3));
-    assert!(!t.contains(4));
+    assert!(!t.contains(&0));
+    assert!(t.contains(&1));
+    assert!(t.contains(&2));
+    assert!(t.contains(&3));
+    assert!(!t.contains(&4));
 }
 
 #[test]
@@ -52,18 +50,18 @@ fn test_delete(){
     t.insert(1, data);
     t.insert(2, data+1);
     t.insert(3, data+2);
-    t.delete(1);
-    assert!(!t.contains(1));
-    assert!(t.contains(2));
-    assert!(t.contains(3));
-    t.delete(2);
-    assert!(!t.contains(1));
-    assert!(!t.contains(2));
-    assert!(t.contains(3));
-    t.delete(3);
-    assert!(!t.contains(1));
-    assert!(!t.contains(2));
-    assert!(!t.contains(3));
+    t.delete(&1);
+    assert!(!t.contains(&1));
+    assert!(t.contains(&2));
+    assert!(t.contains(&3));
+    t.delete(&2);
+    assert!(!t.contains(&1));
+    assert!(!t.contains(&2));
+    assert!(t.contains(&3));
+    t.delete(&3);
+    assert!(!t.contains(&1));
+    assert!(!t.contains(&2));
+    assert!(!t.contains(&3));
     assert!(t.empty());
 }
 
@@ -75,8 +73,8 @@ fn test_perfomance(){
     for _ in 1..10000 {
         t.insert(1, data);
         t.insert(20000, data+1);
-        t.delete(1);
-        t.delete(20000);
+        t.delete(&1);
+        t.delete(&20000);
     }
     let diff_simple = start.elapsed();
     for x in 5..2000 {
@@ -87,8 +85,8 @@ fn test_perfomance(){
     for _ in 1..10000 {
         t.insert(1, data);
         t.insert(20000, data+1);
-        t.delete(1);
-        t.delete(20000);
+        t.delete(&1);
+        t.delete(&20000);
     }
     let diff_full = start_2.elapsed();
     assert!(diff_full < diff_simple * 13); //log time 
@@ -97,15 +95,15 @@ fn test_perfomance(){
 #[test]
 fn test_min(){
     let mut t = avl_tree::AVLTree::<u64,i32>::new();
-    assert!{t.min().is_none()};
+    assert!{t.first_key_value().is_none()};
     t.insert(50,1337);
-    assert_eq!{t.min().expect("get 1 min"),(&50,&1337)};
+    assert_eq!{t.first_key_value().expect("get 1 min"),(&50,&1337)};
     t.insert(49,1338);
-    assert_eq!{t.min().expect("get 2 min"),(&49,&1338)};
+    assert_eq!{t.first_key_value().expect("get 2 min"),(&49,&1338)};
     t.insert(47,1339);
-    assert_eq!{t.min().expect("get 2 min"),(&47,&1339)};
+    assert_eq!{t.first_key_value().expect("get 2 min"),(&47,&1339)};
     t.insert(48,1340);
-    assert_eq!{t.min().expect("get 2 min"),(&47,&1339)};
+    assert_eq!{t.first_key_value().expect("get 2 min"),(&47,&1339)};
 }
 
 #[test]
@@ -131,7 +129,7 @@ fn test_range_iter(){
     t.insert(34,1338);
     t.insert(36,1339);
     t.insert(38,1340);
-    for (i,pair) in t.range(Bound::Unbounded, Bound::Unbounded).enumerate() {
+    for (i,pair) in t.range::<u64,_>(..).enumerate() {
         let (k,v) = pair;
         println!("{}, {}",k,v);
         assert_eq!(k,&((i as u64)*2 +32));
@@ -139,7 +137,7 @@ fn test_range_iter(){
         assert!(i<4);
     }
     println!("included");
-    for (i,pair) in t.range(Bound::Included(34), Bound::Included(36)).enumerate() {
+    for (i,pair) in t.range(34..=36).enumerate() {
         let (k,v) = pair;
         println!("{}, {}",k,v);
         assert_eq!(k,&((i as u64)*2 +34));
@@ -148,7 +146,7 @@ fn test_range_iter(){
     }
 
     println!("excluded");
-    for (i,pair) in t.range(Bound::Excluded(32), Bound::Excluded(38)).enumerate() {
+    for (i,pair) in t.range((Bound::Excluded(32), Bound::Excluded(38))).enumerate() {
         let (k,v) = pair;
         println!("{}, {}",k,v);
         assert_eq!(k,&((i as u64)*2 +34));
@@ -156,3 +154,944 @@ fn test_range_iter(){
         assert!(i<2);
     }
 }
+
+#[test]
+fn test_entry(){
+    let mut t = avl_tree::AVLTree::<u64,i32>::new();
+    for x in 0..100 {
+        *t.entry(x%10).or_insert(0) += 1;
+    }
+    for x in 0..10 {
+        assert_eq!(t.get(&x), Some(&10));
+    }
+    t.entry(3).and_modify(|v| *v = 1337).or_insert(0);
+    assert_eq!(t.get(&3), Some(&1337));
+    t.entry(20).and_modify(|v| *v = 1337).or_insert_with(|| 42);
+    assert_eq!(t.get(&20), Some(&42));
+    assert_eq!(t.entry(20).key(), &20);
+    match t.entry(5) {
+        avl_tree::Entry::Occupied(mut entry) => {
+            assert_eq!(entry.insert(7), 10);
+            assert_eq!(entry.get(), &7);
+            assert_eq!(entry.remove(), 7);
+        },
+        avl_tree::Entry::Vacant(_) => panic!("key 5 should be occupied")
+    }
+    assert!(!t.contains(&5));
+    match t.entry(5) {
+        avl_tree::Entry::Occupied(_) => panic!("key 5 should be vacant"),
+        avl_tree::Entry::Vacant(entry) => assert_eq!(entry.insert(9), &mut 9)
+    }
+    assert_eq!(t.get(&5), Some(&9));
+}
+
+//a key that can not be cloned, so entries have to work without searching their key again
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct Id(u64);
+
+fn check_entry_without_clone<P: avl_tree::BalancePolicy>(mut t: avl_tree::AVLTree<Id,u64,P>){
+    for x in 0..2000 {
+        let key = (x * 7919) % 1000;
+        let data = t.entry(Id(key)).or_insert(0);
+        *data += key;
+    }
+    assert_eq!(t.len(), 1000);
+    assert!(t.iter().all(|(key, data)| *data == 2 * key.0));
+    let mut first = t.first_entry().unwrap();
+    assert_eq!(first.key(), &Id(0));
+    *first.get_mut() = 1;
+    assert_eq!(t.last_entry().unwrap().remove_entry(), (Id(999), 1998));
+    for x in 0..500 {
+        if let avl_tree::Entry::Occupied(entry) = t.entry(Id(x * 2)) {
+            assert_eq!(entry.key(), &Id(x * 2));
+            entry.remove();
+        }
+    }
+    assert_eq!(t.len(), 499);
+    assert!(t.iter().map(|(key, _)| key.0).eq((0..499).map(|x| x * 2 + 1)));
+}
+
+#[test]
+fn test_entry_without_clone(){
+    check_entry_without_clone(avl_tree::AVLTree::new());
+    check_entry_without_clone(avl_tree::AVLTree::with_policy(avl_tree::Wavl));
+    check_entry_without_clone(avl_tree::AVLTree::with_policy(avl_tree::WeightBalanced));
+}
+
+#[test]
+fn test_get_mut(){
+    let mut t = avl_tree::AVLTree::<u64,i32>::new();
+    assert!(t.get_mut(&1).is_none());
+    t.insert(1, 1337);
+    t.insert(2, 1338);
+    *t.get_mut(&1).expect("key 1 is present") += 1;
+    assert_eq!(t.get(&1), Some(&1338));
+    assert_eq!(t.get(&2), Some(&1338));
+    assert!(t.get_mut(&3).is_none());
+}
+
+#[test]
+fn test_mut_iters(){
+    let mut t = avl_tree::AVLTree::<u64,i32>::new();
+    t.insert(32,1337);
+    t.insert(34,1338);
+    t.insert(36,1339);
+    t.insert(38,1340);
+    for (i,(k,v)) in t.iter_mut().enumerate() {
+        assert_eq!(k,&((i as u64)*2 +32));
+        *v += 10;
+    }
+    for (i,(k,v)) in t.range_mut((Bound::Excluded(32), Bound::Included(36))).enumerate() {
+        assert_eq!(k,&((i as u64)*2 +34));
+        assert!(i<2);
+        *v += 100;
+    }
+    for v in t.values_mut() {
+        *v -= 1337;
+    }
+    let values : Vec<i32> = t.iter().map(|(_,v)| *v).collect();
+    assert_eq!(values, vec![10, 111, 112, 13]);
+}
+
+#[test]
+fn test_remove(){
+    let mut t = avl_tree::AVLTree::<u64,String>::new();
+    for x in 0..20 {
+        t.insert(x, x.to_string());
+    }
+    for x in 0..20 {
+        if x%2 == 0 {
+            assert_eq!(t.remove(&x), Some(x.to_string()));
+        } else {
+            assert_eq!(t.remove_entry(&x), Some((x, x.to_string())));
+        }
+        assert!(!t.contains(&x));
+        assert_eq!(t.remove(&x), None);
+        assert_eq!(t.remove_entry(&x), None);
+        assert!(!t.delete(&x));
+    }
+    assert!(t.empty());
+    t.insert(1, "one".to_string());
+    assert!(t.delete(&1));
+    assert!(t.empty());
+}
+
+#[test]
+fn test_len(){
+    let mut t = avl_tree::AVLTree::<u64,i32>::new();
+    assert_eq!(t.len(), 0);
+    for x in 0..100 {
+        t.insert(x, 1337);
+        assert_eq!(t.len(), (x+1) as usize);
+    }
+    for x in 0..100 {
+        t.insert(x, 1338);
+        assert_eq!(t.len(), 100);
+    }
+    *t.entry(100).or_insert(0) += 1;
+    *t.entry(100).or_insert(0) += 1;
+    assert_eq!(t.len(), 101);
+    t.delete(&100);
+    t.delete(&100);
+    assert_eq!(t.len(), 100);
+    for x in 0..50 {
+        t.remove(&x);
+        t.remove_entry(&x);
+    }
+    assert_eq!(t.len(), 50);
+    assert_eq!(t.len(), t.iter().count());
+}
+
+#[test]
+fn test_borrowed_keys(){
+    let mut t = avl_tree::AVLTree::<String,i32>::new();
+    for (i,word) in ["delta", "alpha", "echo", "charlie", "bravo"].iter().enumerate() {
+        t.insert(word.to_string(), i as i32);
+    }
+    assert_eq!(t.get("alpha"), Some(&1));
+    assert_eq!(t.get_or("foxtrot", &-1), &-1);
+    assert!(t.contains("echo"));
+    assert!(!t.contains("foxtrot"));
+    *t.get_mut("delta").expect("delta is present") += 10;
+    assert_eq!(t.get("delta"), Some(&10));
+    *t.entry("golf".to_string()).or_insert(0) += 6;
+    assert_eq!(t.get("golf"), Some(&6));
+    let keys : Vec<&str> = t.range::<str,_>((Bound::Excluded("alpha"), Bound::Included("delta")))
+        .map(|(k,_)| k.as_str()).collect();
+    assert_eq!(keys, vec!["bravo", "charlie", "delta"]);
+    assert_eq!(t.remove("bravo"), Some(4));
+    assert_eq!(t.remove_entry("charlie"), Some(("charlie".to_string(), 3)));
+    assert!(t.delete("alpha"));
+    assert!(!t.delete("alpha"));
+    assert_eq!(t.len(), 3);
+}
+
+#[test]
+fn test_into_iter(){
+    let mut t = avl_tree::AVLTree::<u64,String>::new();
+    for x in (0..50).rev() {
+        t.insert(x, x.to_string());
+    }
+    let pairs : Vec<(u64,String)> = t.into_iter().collect();
+    assert_eq!(pairs.len(), 50);
+    for (i,(k,v)) in pairs.into_iter().enumerate() {
+        assert_eq!(k, i as u64);
+        assert_eq!(v, i.to_string());
+    }
+
+    let mut t = avl_tree::AVLTree::<u64,String>::new();
+    for x in 0..10 {
+        t.insert(x, x.to_string());
+    }
+    let mut drain = t.drain();
+    assert_eq!(drain.size_hint(), (10, Some(10)));
+    assert_eq!(drain.next(), Some((0, "0".to_string())));
+    drop(drain);
+    assert!(t.empty());
+    assert_eq!(t.len(), 0);
+
+    for x in 0..10 {
+        t.insert(x, x.to_string());
+    }
+    let keys : Vec<u64> = t.into_keys().collect();
+    assert_eq!(keys, (0..10).collect::<Vec<u64>>());
+
+    let mut t = avl_tree::AVLTree::<u64,String>::new();
+    for x in 0..10 {
+        t.insert(x, x.to_string());
+    }
+    let values : Vec<String> = t.into_values().collect();
+    assert_eq!(values, (0..10).map(|x| x.to_string()).collect::<Vec<String>>());
+
+    struct Pending {
+        pairs: avl_tree::IntoIter<u64,u64>,
+    }
+    fn range_of<'a>(t: &'a avl_tree::AVLTree<u64,u64>) -> avl_tree::RangePairIter<'a,u64,u64> {
+        t.range(2..4)
+    }
+    let mut t = avl_tree::AVLTree::<u64,u64>::new();
+    for x in 0..5 {
+        t.insert(x, x);
+    }
+    assert_eq!(range_of(&t).map(|(k,_)| *k).collect::<Vec<u64>>(), vec![2,3]);
+    let mut pending = Pending{pairs: t.into_iter()};
+    assert_eq!(pending.pairs.next(), Some((0,0)));
+}
+
+#[test]
+fn test_rev_range_iter(){
+    let mut t = avl_tree::AVLTree::<u64,i32>::new();
+    for x in 0..100 {
+        t.insert(x, x as i32);
+    }
+    let last : Vec<u64> = t.range(..50).rev().take(10).map(|(k,_)| *k).collect();
+    assert_eq!(last, vec![49,48,47,46,45,44,43,42,41,40]);
+    let mut iter = t.range(10..=13);
+    assert_eq!(iter.next_back(), Some((&13,&13)));
+    assert_eq!(iter.next(), Some((&10,&10)));
+    assert_eq!(iter.next_back(), Some((&12,&12)));
+    assert_eq!(iter.next(), Some((&11,&11)));
+    assert_eq!(iter.next_back(), None);
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+fn test_neighbour_queries(){
+    let mut t = avl_tree::AVLTree::<u64,i32>::new();
+    assert!(t.floor(&10).is_none());
+    assert!(t.ceiling(&10).is_none());
+    assert!(t.lower(&10).is_none());
+    assert!(t.higher(&10).is_none());
+    for x in 1..50 {
+        t.insert(x*10, x as i32);
+    }
+    for key in 0..600 {
+        let expected : Vec<u64> = (1..50).map(|x| x*10).collect();
+        assert_eq!(t.floor(&key).map(|(k,_)| *k), expected.iter().cloned().rev().find(|k| *k <= key));
+        assert_eq!(t.lower(&key).map(|(k,_)| *k), expected.iter().cloned().rev().find(|k| *k < key));
+        assert_eq!(t.ceiling(&key).map(|(k,_)| *k), expected.iter().cloned().find(|k| *k >= key));
+        assert_eq!(t.higher(&key).map(|(k,_)| *k), expected.iter().cloned().find(|k| *k > key));
+    }
+    assert_eq!(t.floor(&255), Some((&250,&25)));
+    assert_eq!(t.higher(&250), Some((&260,&26)));
+}
+
+#[test]
+fn test_pop_min_max(){
+    let mut t = avl_tree::AVLTree::<u64,String>::new();
+    assert!(t.pop_min().is_none());
+    assert!(t.pop_max().is_none());
+    assert!(t.first_entry().is_none());
+    assert!(t.last_entry().is_none());
+    for x in 0..100 {
+        t.insert(x, x.to_string());
+    }
+    for x in 0..50 {
+        assert_eq!(t.pop_min(), Some((x, x.to_string())));
+        assert_eq!(t.pop_max(), Some((99-x, (99-x).to_string())));
+        assert_eq!(t.len(), (98-2*x) as usize);
+    }
+    assert!(t.empty());
+
+    for x in 0..10 {
+        t.insert(x, x.to_string());
+    }
+    t.first_entry().expect("tree is not empty").get_mut().push('!');
+    assert_eq!(t.get(&0).map(|s| s.as_str()), Some("0!"));
+    assert_eq!(t.first_entry().expect("tree is not empty").key(), &0);
+    assert_eq!(t.last_entry().expect("tree is not empty").get(), "9");
+    assert_eq!(t.last_entry().expect("tree is not empty").remove_entry(), (9, "9".to_string()));
+    assert_eq!(t.first_entry().expect("tree is not empty").remove(), "0!".to_string());
+    assert_eq!(t.len(), 8);
+    assert_eq!(t.first_key_value(), Some((&1, &"1".to_string())));
+    assert_eq!(t.last_key_value(), Some((&8, &"8".to_string())));
+}
+
+#[test]
+fn test_traits(){
+    use std::collections::BTreeMap;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of(t: &avl_tree::AVLTree<u64,i32>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        t.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let empty : avl_tree::AVLTree<u64,i32> = Default::default();
+    assert!(empty.empty());
+    assert_eq!(format!("{:?}", empty), "{}");
+
+    //same content inserted in different orders gives differently shaped but equal trees
+    let ascending : avl_tree::AVLTree<u64,i32> = (0..20).map(|x| (x, x as i32)).collect();
+    let mut descending = avl_tree::AVLTree::<u64,i32>::new();
+    descending.extend((0..20).rev().map(|x| (x, x as i32)));
+    assert!(ascending == descending);
+    assert_eq!(hash_of(&ascending), hash_of(&descending));
+    assert_eq!(ascending.partial_cmp(&descending), Some(std::cmp::Ordering::Equal));
+    assert_eq!(ascending.cmp(&descending), std::cmp::Ordering::Equal);
+
+    let mut cloned = ascending.clone();
+    assert!(cloned == ascending);
+    cloned.insert(5, 1337);
+    assert!(cloned != ascending);
+    assert!(cloned > ascending);
+    assert_eq!(ascending[&5], 5);
+    assert_eq!(cloned[&5], 1337);
+    cloned.delete(&19);
+    assert!(cloned > descending);
+    assert_eq!(cloned.cmp(&descending), std::cmp::Ordering::Greater);
+    assert!(cloned.clone().max(descending.clone()) == cloned);
+    assert_eq!(cloned.len(), 19);
+
+    let small : avl_tree::AVLTree<i32,&str> = avl_tree::AVLTree::from([(2,"b"), (1,"a"), (2,"c")]);
+    assert_eq!(format!("{:?}", small), "{1: \"a\", 2: \"c\"}");
+    assert_eq!(small.len(), 2);
+
+    let mut map = BTreeMap::new();
+    map.insert(3, 30);
+    map.insert(1, 10);
+    let from_map : avl_tree::AVLTree<i32,i32> = avl_tree::AVLTree::from(map);
+    assert_eq!(from_map.iter().collect::<Vec<_>>(), vec![(&1,&10), (&3,&30)]);
+
+    let mut sum = 0;
+    for (_, v) in &from_map {
+        sum += *v;
+    }
+    assert_eq!(sum, 40);
+    let mut from_map = from_map;
+    for (_, v) in &mut from_map {
+        *v += 1;
+    }
+    assert_eq!(from_map[&3], 31);
+}
+
+#[test]
+#[should_panic]
+fn test_index_missing_key(){
+    let t = avl_tree::AVLTree::<i32,i32>::from([(1,10)]);
+    let _ = t[&2];
+}
+
+#[test]
+fn test_clear_retain_extract_if(){
+    let mut t: avl_tree::AVLTree<u64,i32> = (0..100).map(|k| (k, k as i32)).collect();
+    t.retain(|k, v| { *v += 1; k % 3 != 0 });
+    assert_eq!(t.len(), 66);
+    for (k, v) in t.iter() {
+        assert!(k % 3 != 0);
+        assert_eq!(*v, *k as i32 + 1);
+    }
+
+    let extracted : Vec<u64> = t.extract_if((Bound::Excluded(10), Bound::Included(50)), |k, _| k % 2 == 0).map(|(k,_)| k).collect();
+    assert_eq!(extracted, (11..51).filter(|k| k % 3 != 0 && k % 2 == 0).collect::<Vec<u64>>());
+    assert_eq!(t.len(), 66 - extracted.len());
+    assert_eq!(t.iter().count(), t.len());
+    for k in 0..100 {
+        assert_eq!(t.contains(&k), k % 3 != 0 && (k <= 10 || k > 50 || k % 2 == 1));
+    }
+
+    //only the pairs the iterator returned are removed
+    let len = t.len();
+    let unconsumed = t.extract_if::<u64,_,_>(.., |k, _| *k < 20);
+    drop(unconsumed);
+    assert_eq!(t.len(), len);
+    assert_eq!(t.first_key_value().unwrap().0, &1);
+    let mut partly = t.extract_if::<u64,_,_>(.., |k, _| *k < 20);
+    assert_eq!(partly.next(), Some((1, 2)));
+    assert_eq!(partly.next(), Some((2, 3)));
+    drop(partly);
+    assert_eq!(t.len(), len - 2);
+    assert_eq!(t.iter().count(), len - 2);
+    assert_eq!(t.first_key_value().unwrap().0, &4);
+    t.insert(0, 0);
+    assert_eq!(t.first_key_value().unwrap().0, &0);
+
+    t.retain(|_, _| false);
+    assert!(t.empty());
+    t.insert(1, 1);
+    t.clear();
+    assert!(t.empty());
+    assert_eq!(t.len(), 0);
+    assert!(t.extract_if::<u64,_,_>(.., |_, _| true).next().is_none());
+}
+
+#[test]
+fn test_extract_if_panicking_predicate(){
+    use std::panic::{self, AssertUnwindSafe};
+    let mut t: avl_tree::AVLTree<u64,i32,avl_tree::Wavl> = (0..100).map(|k| (k, k as i32)).collect();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        t.extract_if(20..60, |k, v| {
+            if *k == 40 { panic!("predicate failed") }
+            *v = -1;
+            k % 2 == 0
+        }).count()
+    }));
+    assert!(result.is_err());
+    //the even keys before the panic are gone, all other pairs are still there
+    assert_eq!(t.len(), 90);
+    assert_eq!(t.iter().count(), 90);
+    for k in 0..100 {
+        assert_eq!(t.contains(&k), !(20..40).contains(&k) || k % 2 == 1);
+    }
+    assert_eq!(t.get(&21), Some(&-1));
+    assert_eq!(t.get(&40), Some(&40));
+    assert!(t.iter().zip(t.iter().skip(1)).all(|(a, b)| a.0 < b.0));
+    t.insert(20, 20);
+    assert_eq!(t.range(19..22).count(), 3);
+}
+
+#[test]
+fn test_split(){
+    let mut t: avl_tree::AVLTree<u64,i32> = (0..200).map(|k| (k*2, k as i32)).collect();
+    let upper = t.split_off(&101);
+    assert_eq!(t.len(), 51);
+    assert_eq!(t.iter().count(), 51);
+    assert_eq!(t.last_key_value(), Some((&100,&50)));
+    assert_eq!(upper.len(), 149);
+    assert_eq!(upper.iter().map(|(k,_)| *k).collect::<Vec<u64>>(), (51..200).map(|k| k*2).collect::<Vec<u64>>());
+
+    let mut all = upper.clone();
+    let none = all.split_off(&0);
+    assert!(all.empty());
+    assert_eq!(none.len(), 149);
+    let mut all = none;
+    let past_end = all.split_off(&1000);
+    assert!(past_end.empty());
+    assert_eq!(all.len(), 149);
+
+    let (smaller, pair, bigger) = upper.split(&300);
+    assert_eq!(smaller.len(), 99);
+    assert_eq!(smaller.last_key_value(), Some((&298,&149)));
+    assert_eq!(pair, Some((300,150)));
+    assert_eq!(bigger.len(), 49);
+    assert_eq!(bigger.first_key_value(), Some((&302,&151)));
+
+    let (smaller, pair, bigger) = bigger.split(&303);
+    assert_eq!((smaller.len(), pair, bigger.len()), (1, None, 48));
+
+    let mut strings: avl_tree::AVLTree<String,i32> = ["a","b","c","d"].iter().map(|s| (s.to_string(), 0)).collect();
+    let tail = strings.split_off("c");
+    assert_eq!(tail.iter().map(|(k,_)| k.as_str()).collect::<Vec<&str>>(), vec!["c","d"]);
+    assert_eq!(strings.len(), 2);
+}
+
+#[test]
+fn test_append(){
+    let mut t: avl_tree::AVLTree<u64,i32> = (0..300).map(|k| (k, 0)).collect();
+    let mut small: avl_tree::AVLTree<u64,i32> = (300..303).map(|k| (k, 1)).collect();
+    t.append(&mut small);
+    assert!(small.empty());
+    assert_eq!(small.len(), 0);
+    assert_eq!(t.len(), 303);
+    assert_eq!(t.iter().map(|(k,_)| *k).collect::<Vec<u64>>(), (0..303).collect::<Vec<u64>>());
+
+    let mut before: avl_tree::AVLTree<u64,i32> = (1000..1001).map(|k| (k, 2)).collect();
+    let mut lower: avl_tree::AVLTree<u64,i32> = (500..600).map(|k| (k, 3)).collect();
+    before.append(&mut lower);
+    assert_eq!(before.len(), 101);
+    assert_eq!(before.first_key_value(), Some((&500,&3)));
+    assert_eq!(before.last_key_value(), Some((&1000,&2)));
+
+    let mut overlapping: avl_tree::AVLTree<u64,i32> = (0..400).filter(|k| k % 2 == 0).map(|k| (k, 4)).collect();
+    t.append(&mut overlapping);
+    assert_eq!(t.len(), 303 + (303..400).filter(|k| k % 2 == 0).count());
+    assert_eq!(t.iter().count(), t.len());
+    assert_eq!(t.get(&2), Some(&4));
+    assert_eq!(t.get(&3), Some(&0));
+    assert_eq!(t.get(&301), Some(&1));
+    assert_eq!(t.get(&302), Some(&4));
+    assert_eq!(t.get(&398), Some(&4));
+
+    let mut empty = avl_tree::AVLTree::<u64,i32>::new();
+    empty.append(&mut t);
+    assert!(t.empty());
+    assert_eq!(empty.len(), 351);
+    empty.append(&mut t);
+    assert_eq!(empty.len(), 351);
+}
+
+#[test]
+fn test_set_operations(){
+    use std::collections::BTreeMap;
+    for _ in 0..20 {
+        let left_keys : Vec<u16> = (0..rand::random::<u16>() % 300).map(|_| rand::random::<u16>() % 500).collect();
+        let right_keys : Vec<u16> = (0..rand::random::<u16>() % 30).map(|_| rand::random::<u16>() % 500).collect();
+        let left: avl_tree::AVLTree<u16,u32> = left_keys.iter().map(|k| (*k, *k as u32)).collect();
+        let right: avl_tree::AVLTree<u16,u32> = right_keys.iter().map(|k| (*k, 1000)).collect();
+        let left_map: BTreeMap<u16,u32> = left_keys.iter().map(|k| (*k, *k as u32)).collect();
+        let right_map: BTreeMap<u16,u32> = right_keys.iter().map(|k| (*k, 1000)).collect();
+
+        let mut united = left.clone();
+        united.union_with(right.clone(), |_, a, b| a + b);
+        let mut expected = left_map.clone();
+        for (k, v) in right_map.iter() {
+            *expected.entry(*k).or_insert(0) += *v;
+        }
+        assert_eq!(united.len(), expected.len());
+        assert!(united.iter().eq(expected.iter()));
+
+        let mut common = right.clone();
+        common.intersection_with(left.clone(), |_, a, b| a + b);
+        let expected: Vec<(u16,u32)> = right_map.iter().filter(|&(k,_)| left_map.contains_key(k)).map(|(k,v)| (*k, v + *k as u32)).collect();
+        assert_eq!(common.len(), expected.len());
+        assert_eq!(common.into_iter().collect::<Vec<(u16,u32)>>(), expected);
+
+        let mut only_left = left.clone();
+        only_left.difference(&right);
+        let expected: Vec<(u16,u32)> = left_map.iter().filter(|&(k,_)| !right_map.contains_key(k)).map(|(k,v)| (*k, *v)).collect();
+        assert_eq!(only_left.len(), expected.len());
+        assert_eq!(only_left.into_iter().collect::<Vec<(u16,u32)>>(), expected);
+
+        let mut either = right.clone();
+        either.symmetric_difference(left.clone());
+        let mut expected: Vec<u16> = left_map.keys().chain(right_map.keys()).filter(|k| left_map.contains_key(k) != right_map.contains_key(k)).cloned().collect();
+        expected.sort();
+        assert_eq!(either.len(), expected.len());
+        assert_eq!(either.into_keys().collect::<Vec<u16>>(), expected);
+    }
+}
+
+#[test]
+fn test_delete_range(){
+    let bounds = [Bound::Unbounded, Bound::Included(0), Bound::Excluded(0), Bound::Included(13), Bound::Excluded(13),
+                  Bound::Included(14), Bound::Excluded(14), Bound::Included(98), Bound::Excluded(98), Bound::Included(150)];
+    for lower in bounds.iter() {
+        for upper in bounds.iter() {
+            let mut t: avl_tree::AVLTree<u64,i32> = (0..50).map(|k| (k*2, k as i32)).collect();
+            let removed = t.delete_range(lower.as_ref(), upper.as_ref());
+            let in_range = |k: &u64| {
+                let above = match *lower { Bound::Included(b) => *k >= b, Bound::Excluded(b) => *k > b, Bound::Unbounded => true };
+                let below = match *upper { Bound::Included(b) => *k <= b, Bound::Excluded(b) => *k < b, Bound::Unbounded => true };
+                above && below
+            };
+            let expected_removed : Vec<u64> = (0..50).map(|k| k*2).filter(|k| in_range(k)).collect();
+            let expected_kept : Vec<u64> = (0..50).map(|k| k*2).filter(|k| !in_range(k)).collect();
+            assert_eq!(removed.len(), expected_removed.len());
+            assert_eq!(removed.into_keys().collect::<Vec<u64>>(), expected_removed);
+            assert_eq!(t.len(), expected_kept.len());
+            assert_eq!(t.into_keys().collect::<Vec<u64>>(), expected_kept);
+        }
+    }
+
+    let mut t: avl_tree::AVLTree<String,i32> = ["a","b","c","d"].iter().map(|s| (s.to_string(), 0)).collect();
+    let removed = t.delete_range(Bound::Excluded("a"), Bound::Included("c"));
+    assert_eq!(removed.len(), 2);
+    assert_eq!(t.into_keys().collect::<Vec<String>>(), vec!["a".to_string(), "d".to_string()]);
+}
+
+#[test]
+fn test_from_sorted_iter(){
+    for len in [0u64, 1, 2, 3, 100, 1023, 1024, 5000].iter().cloned() {
+        let t : avl_tree::AVLTree<u64,u64> = avl_tree::AVLTree::from_sorted_iter((0..len).map(|k| (k*3, k))).unwrap();
+        assert_eq!(t.len(), len as usize);
+        assert_eq!(t.iter().count(), len as usize);
+        for k in 0..len {
+            assert_eq!(t.get(&(k*3)), Some(&k));
+            assert!(!t.contains(&(k*3+1)));
+        }
+        let unchecked = avl_tree::AVLTree::from_sorted_iter_unchecked((0..len).map(|k| (k*3, k)));
+        assert!(unchecked == t);
+    }
+
+    let mut t : avl_tree::AVLTree<u64,i32> = avl_tree::AVLTree::from_sorted_iter((0..10u64).map(|k| (k, 0))).unwrap();
+    t.insert(100, 1);
+    assert!(t.delete(&5));
+    assert_eq!(t.len(), 10);
+
+    let duplicate = avl_tree::AVLTree::<_,_>::from_sorted_iter(vec![(1,'a'), (2,'b'), (2,'c')]);
+    let error = duplicate.unwrap_err();
+    assert_eq!(error.position(), 2);
+    assert_eq!(error.to_string(), "the key at position 2 is not bigger than the key before it");
+    let descending = avl_tree::AVLTree::<_,_>::from_sorted_iter(vec![(2,'a'), (1,'b')]);
+    assert_eq!(descending.unwrap_err().position(), 1);
+}
+
+#[test]
+fn test_insert_batch(){
+    use std::collections::BTreeMap;
+    let mut t: avl_tree::AVLTree<u16,u32> = (0..1000).map(|k| (k*3, 0)).collect();
+    let mut model: BTreeMap<u16,u32> = (0..1000).map(|k| (k*3, 0)).collect();
+    for round in 1..6 {
+        let batch : Vec<(u16,u32)> = (0..200).map(|i| (rand::random::<u16>() % 4000, round*1000 + i)).collect();
+        for &(k, v) in batch.iter() {
+            model.insert(k, v);
+        }
+        t.insert_batch(batch);
+        assert_eq!(t.len(), model.len());
+        assert!(t.iter().eq(model.iter()));
+    }
+
+    let batch = vec![(3,1), (1,1), (3,2), (3,3), (7,1)];
+    let mut summed: avl_tree::AVLTree<u16,u32> = avl_tree::AVLTree::from([(3,100), (5,100)]);
+    summed.insert_batch_with(batch.clone(), |_, a, b| a + b);
+    assert_eq!(summed.into_iter().collect::<Vec<(u16,u32)>>(), vec![(1,1), (3,106), (5,100), (7,1)]);
+
+    let mut ordered: avl_tree::AVLTree<u16,Vec<u32>> = avl_tree::AVLTree::from([(3,vec![0])]);
+    ordered.insert_batch_with(batch.iter().map(|&(k,v)| (k, vec![v])).collect(), |_, mut a, b| { a.extend(b); a });
+    assert_eq!(ordered.get(&3), Some(&vec![0,1,2,3]));
+
+    let mut first_wins: avl_tree::AVLTree<u16,u32> = avl_tree::AVLTree::from([(7,100)]);
+    first_wins.insert_batch_with(batch, |_, a, _| a);
+    assert_eq!(first_wins.into_iter().collect::<Vec<(u16,u32)>>(), vec![(1,1), (3,1), (7,100)]);
+
+    let mut empty = avl_tree::AVLTree::<u16,u32>::new();
+    empty.insert_batch(vec![]);
+    assert!(empty.empty());
+}
+
+#[test]
+fn test_push_back(){
+    let mut t = avl_tree::AVLTree::<u64,i32>::new();
+    for key in 0..2000 {
+        t.push_back(key*2, key as i32).unwrap();
+    }
+    assert_eq!(t.len(), 2000);
+    assert_eq!(t.iter().count(), 2000);
+    for key in 0..2000 {
+        assert_eq!(t.get(&(key*2)), Some(&(key as i32)));
+    }
+    assert!(t.delete(&10));
+
+    let error = t.push_back(3998, -1).unwrap_err();
+    assert_eq!(error.key(), &3998);
+    assert_eq!(error.to_string(), "the key is not bigger than the maximal key of the tree");
+    assert_eq!(t.push_back(11, -1).unwrap_err().into_pair(), (11, -1));
+    assert_eq!(t.get(&3998), Some(&1999));
+    assert_eq!(t.len(), 1999);
+
+    t.push_back(3999, -1).unwrap();
+    assert_eq!(t.last_key_value(), Some((&3999, &-1)));
+    assert_eq!(t.len(), 2000);
+}
+
+#[test]
+fn test_finger(){
+    use std::collections::BTreeMap;
+    let mut t = avl_tree::AVLTree::<u64,i32>::new();
+    let mut model = BTreeMap::new();
+    {
+        let mut finger = t.finger();
+        for key in 0..2000 {
+            finger.push_back(key*2, key as i32).unwrap();
+            model.insert(key*2, key as i32);
+        }
+        assert_eq!(finger.push_back(3998, -1).unwrap_err().into_pair(), (3998, -1));
+        for key in (1001..1100).step_by(2) {
+            assert_eq!(finger.insert(key, -1), None);
+            model.insert(key, -1);
+        }
+        assert_eq!(finger.insert(1000, -2), Some(500));
+        model.insert(1000, -2);
+        finger.push_back(4000, -3).unwrap();
+        model.insert(4000, -3);
+        assert_eq!(finger.insert(7, -4), None);
+        model.insert(7, -4);
+    }
+    assert_eq!(t.len(), model.len());
+    assert!(t.iter().eq(model.iter()));
+    t.push_back(4001, 0).unwrap();
+    assert_eq!(t.last_key_value(), Some((&4001, &0)));
+}
+
+#[test]
+fn test_cursor(){
+    use std::ops::RangeBounds;
+    let t : avl_tree::AVLTree<u64,i32> = (0..200).map(|k| (k*2, k as i32)).collect();
+    let keys : Vec<u64> = (0..200).map(|k| k*2).collect();
+    let bounds = [Bound::Unbounded, Bound::Included(0), Bound::Excluded(0), Bound::Included(77), Bound::Excluded(77),
+                  Bound::Included(150), Bound::Excluded(150), Bound::Included(398), Bound::Excluded(398), Bound::Included(500)];
+    for bound in bounds.iter() {
+        let lower = keys.iter().position(|k| (*bound, Bound::Unbounded).contains(k));
+        let upper = keys.iter().rposition(|k| (Bound::Unbounded, *bound).contains(k));
+        for &(from_lower, forward) in [(true, true), (true, false), (false, true), (false, false)].iter() {
+            let (mut cursor, start) = if from_lower {
+                (t.lower_bound_cursor(bound.as_ref()), lower)
+            } else {
+                (t.upper_bound_cursor(bound.as_ref()), upper)
+            };
+            //walk twice around the tree, through the ghost position
+            let mut position = start;
+            for _ in 0..2*keys.len() + 2 {
+                assert_eq!(cursor.key(), position.map(|p| &keys[p]));
+                assert_eq!(cursor.value().cloned(), position.map(|p| p as i32));
+                let next = match position { None => Some(0), Some(p) if p + 1 < keys.len() => Some(p + 1), _ => None };
+                let prev = match position { None => Some(keys.len() - 1), Some(0) => None, Some(p) => Some(p - 1) };
+                assert_eq!(cursor.peek_next().map(|(k,_)| *k), next.map(|p| keys[p]));
+                assert_eq!(cursor.peek_prev().map(|(k,_)| *k), prev.map(|p| keys[p]));
+                if forward { cursor.move_next(); position = next } else { cursor.move_prev(); position = prev }
+            }
+        }
+    }
+    let empty = avl_tree::AVLTree::<u64,i32>::new();
+    let mut cursor = empty.lower_bound_cursor::<u64>(Bound::Unbounded);
+    cursor.move_next();
+    cursor.move_prev();
+    assert!(cursor.key().is_none() && cursor.peek_next().is_none() && cursor.peek_prev().is_none());
+}
+
+#[test]
+fn test_cursor_mut(){
+    use std::collections::BTreeMap;
+    let mut t = avl_tree::AVLTree::<u64,i32>::new();
+    let mut model = BTreeMap::new();
+    for key in 0..100 {
+        t.insert(key*3, 0);
+        model.insert(key*3, 0);
+    }
+    for round in 0..300 {
+        let start = rand::random::<u64>() % 320;
+        let mut cursor = t.lower_bound_cursor_mut(Bound::Included(&start));
+        let mut current = model.range(start..).next().map(|(k,_)| *k);
+        for _ in 0..20 {
+            assert_eq!(cursor.key().cloned(), current);
+            assert_eq!(cursor.value(), current.map(|k| &model[&k]));
+            let next = match current { Some(k) => model.range(k+1..).next(), None => model.iter().next() }.map(|(k,_)| *k);
+            let prev = match current { Some(k) => model.range(..k).next_back(), None => model.iter().next_back() }.map(|(k,_)| *k);
+            assert_eq!(cursor.peek_next().map(|(k,_)| *k), next);
+            assert_eq!(cursor.peek_prev().map(|(k,_)| *k), prev);
+            let key = rand::random::<u64>() % 320;
+            match rand::random::<u8>() % 6 {
+                0 => { cursor.move_next(); current = next },
+                1 => { cursor.move_prev(); current = prev },
+                2 => if let Some(data) = cursor.value_mut() {
+                    *data = round;
+                    *model.get_mut(&current.unwrap()).unwrap() = round;
+                },
+                3 => {
+                    assert_eq!(cursor.remove_current(), current.map(|k| (k, model.remove(&k).unwrap())));
+                    current = current.and_then(|k| model.range(k..).next().map(|(k,_)| *k));
+                },
+                4 => {
+                    let fits = prev.is_none_or(|p| p < key) && current.is_none_or(|c| key < c);
+                    assert_eq!(cursor.insert_before(key, round).is_ok(), fits);
+                    if fits { model.insert(key, round); }
+                },
+                _ => {
+                    let fits = current.is_none_or(|c| c < key) && next.is_none_or(|n| key < n);
+                    match cursor.insert_after(key, round) {
+                        Ok(()) => { assert!(fits); model.insert(key, round); },
+                        Err(error) => { assert!(!fits); assert_eq!(error.into_pair(), (key, round)) }
+                    }
+                }
+            }
+        }
+        //the cursor puts the tree back together when it is dropped
+        drop(cursor);
+        assert_eq!(t.len(), model.len());
+        assert!(t.iter().eq(model.iter()));
+    }
+    //the random operations may have removed all pairs, a pair above 0 keeps 0 from fitting at the end
+    t.insert(1, 0);
+    let mut cursor = t.upper_bound_cursor_mut(Bound::Unbounded);
+    cursor.move_next();
+    assert!(cursor.key().is_none() && cursor.remove_current().is_none());
+    let error = cursor.insert_before(0, 0).unwrap_err();
+    assert_eq!(error.to_string(), "the key does not lie strictly between the keys around the cursor");
+}
+
+#[test]
+fn test_cursor_merge_ranges(){
+    //ranges stored as start -> end, adjacent or overlapping ranges are merged into their predecessor
+    let mut t : avl_tree::AVLTree<u64,u64> = vec![(0,5), (5,8), (10,12), (11,20), (30,31), (31,32), (40,41)].into_iter().collect();
+    {
+        let mut cursor = t.lower_bound_cursor_mut::<u64>(Bound::Unbounded);
+        while let Some(&end) = cursor.value() {
+            match cursor.peek_next() {
+                Some((&start, &next_end)) if start <= end => {
+                    *cursor.value_mut().unwrap() = end.max(next_end);
+                    cursor.move_next();
+                    cursor.remove_current();
+                    cursor.move_prev();
+                },
+                _ => cursor.move_next()
+            }
+        }
+    }
+    assert_eq!(t.iter().map(|(k,v)| (*k,*v)).collect::<Vec<_>>(), vec![(0,8), (10,20), (30,32), (40,41)]);
+}
+
+#[test]
+fn test_arena_tree(){
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of<T: Hash>(t: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        t.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    //the same random operations on an ArenaAVLTree and an AVLTree give the same results
+    let mut arena = avl_tree::ArenaAVLTree::new();
+    let mut t = avl_tree::AVLTree::new();
+    for round in 0..20000 {
+        let key = rand::random::<u16>() as u64 % 1000;
+        match rand::random::<u8>() % 8 {
+            0 | 1 => { arena.insert(key, round); t.insert(key, round); },
+            2 => assert_eq!(arena.remove(&key), t.remove(&key)),
+            3 => assert_eq!(arena.floor(&key), t.floor(&key)),
+            4 => assert_eq!(arena.higher(&key), t.higher(&key)),
+            5 => assert_eq!((arena.ceiling(&key), arena.lower(&key)), (t.ceiling(&key), t.lower(&key))),
+            6 => {
+                assert_eq!(arena.push_back(key, round).is_ok(), t.push_back(key, round).is_ok());
+                if let Some((max, data)) = arena.pop_max() {
+                    assert_eq!(t.pop_max(), Some((max, data)));
+                    arena.push_back(max, data).unwrap();
+                    t.push_back(max, data).unwrap();
+                }
+            },
+            _ => assert_eq!(arena.range(key..key + 50).rev().collect::<Vec<_>>(), t.range(key..key + 50).rev().collect::<Vec<_>>())
+        }
+        assert_eq!(arena.len(), t.len());
+    }
+    assert!(arena.capacity() <= 2000);
+    for (key, value) in arena.range_mut(100..200) {
+        *value = *key as i32;
+    }
+    for (key, value) in t.range_mut(100..200) {
+        *value = *key as i32;
+    }
+    assert!(arena.iter().eq(t.iter()));
+    arena.retain(|k, _| k % 7 != 0);
+    t.retain(|k, _| k % 7 != 0);
+    arena.shrink_to_fit();
+    assert_eq!(arena.capacity(), t.len());
+    assert_eq!(arena.first_key_value(), t.first_key_value());
+    assert_eq!(arena.last_key_value(), t.last_key_value());
+    assert_eq!(arena.pop_min(), t.pop_min());
+    assert_eq!(arena.pop_max(), t.pop_max());
+    for key in 0..1100 {
+        *arena.entry(key).and_modify(|v| *v += 1).or_insert(key as i32) *= 2;
+        *t.entry(key).and_modify(|v| *v += 1).or_insert(key as i32) *= 2;
+    }
+    assert_eq!(arena.last_entry().unwrap().remove_entry(), t.last_entry().unwrap().remove_entry());
+    assert!(arena.iter().eq(t.iter()));
+    for key in (0..1100).step_by(37) {
+        let (mut a, mut b) = (arena.lower_bound_cursor(Bound::Excluded(&key)), t.lower_bound_cursor(Bound::Excluded(&key)));
+        for _ in 0..60 {
+            assert_eq!((a.key(), a.value(), a.peek_next(), a.peek_prev()), (b.key(), b.value(), b.peek_next(), b.peek_prev()));
+            if key % 2 == 0 { a.move_next(); b.move_next() } else { a.move_prev(); b.move_prev() }
+        }
+        assert_eq!(arena.upper_bound_cursor(Bound::Included(&key)).key(), t.upper_bound_cursor(Bound::Included(&key)).key());
+    }
+    {
+        let (mut a, mut b) = (arena.upper_bound_cursor_mut(Bound::Included(&500)), t.upper_bound_cursor_mut(Bound::Included(&500)));
+        while a.key().is_some() {
+            assert_eq!(a.remove_current(), b.remove_current());
+            a.move_prev();
+            a.move_prev();
+            b.move_prev();
+            b.move_prev();
+        }
+        assert_eq!(b.key(), None);
+    }
+    assert!(arena.iter().eq(t.iter()));
+    assert_eq!(format!("{:?}", arena), format!("{:?}", t));
+    assert!(arena.clone() == arena);
+    assert_eq!(arena.cmp(&arena.clone()), std::cmp::Ordering::Equal);
+    assert_eq!(hash_of(&arena), hash_of(&t));
+    let other : Vec<(u64,i32)> = (0..300).map(|k| (k*5, 7)).collect();
+    arena.union_with(other.iter().cloned().collect(), |_, a, b| a - b);
+    t.union_with(other.iter().cloned().collect(), |_, a, b| a - b);
+    assert!(arena.split_off(&800).iter().eq(t.split_off(&800).iter()));
+    assert!(arena.iter().eq(t.iter()));
+    assert!(arena.extract_if(200..600, |k, _| k % 3 == 0).take(20).eq(t.extract_if(200..600, |k, _| k % 3 == 0).take(20)));
+    assert!(arena.iter().eq(t.iter()));
+    assert!(arena.drain().eq(t.drain()));
+    assert!(arena.is_empty());
+}
+
+//runs random operations on a tree with the given balancing policy and a BTreeMap side by side
+fn check_balance_policy<P: avl_tree::BalancePolicy>(mut t: avl_tree::AVLTree<u64,i32,P>){
+    use std::collections::BTreeMap;
+    let mut model = BTreeMap::new();
+    for round in 0..20000 {
+        let key = rand::random::<u16>() as u64 % 1000;
+        match rand::random::<u8>() % 8 {
+            0 | 1 => { t.insert(key, round); model.insert(key, round); },
+            7 => {
+                let next = model.keys().next_back().map_or(0, |max| max + 1);
+                t.push_back(next, round).unwrap();
+                model.insert(next, round);
+            },
+            2 => assert_eq!(t.remove(&key), model.remove(&key)),
+            3 => { *t.entry(key).or_insert(0) += 1; *model.entry(key).or_insert(0) += 1; },
+            4 => assert_eq!(t.pop_min(), model.keys().next().cloned().map(|min| (min, model.remove(&min).unwrap()))),
+            5 => {
+                let mut cursor = t.lower_bound_cursor_mut(Bound::Included(&key));
+                if cursor.key().is_some_and(|k| k % 2 == 0) {
+                    let (k, _) = cursor.remove_current().unwrap();
+                    model.remove(&k);
+                }
+            },
+            _ => assert!(t.range(key..key + 50).eq(model.range(key..key + 50)))
+        }
+        assert_eq!(t.len(), model.len());
+    }
+    assert!(t.iter().eq(model.iter()));
+
+    let upper = t.split_off(&500);
+    assert!(upper.iter().eq(model.range(500..)));
+    let mut other : avl_tree::AVLTree<u64,i32,P> = (1000..1100).map(|k| (k, 0)).collect();
+    t.append(&mut other);
+    t.union_with(upper, |_, a, _| a);
+    let removed = t.delete_range(Bound::Included(&200), Bound::Excluded(&300));
+    assert!(removed.iter().eq(model.range(200..300)));
+    model.retain(|k, _| !(200..300).contains(k));
+    model.extend((1000..1100).map(|k| (k, 0)));
+    assert!(t.iter().eq(model.iter()));
+
+    let rebuilt = avl_tree::AVLTree::<u64,i32,P>::from_sorted_iter(model.clone()).unwrap();
+    assert!(rebuilt == t);
+    let converted : avl_tree::AVLTree<u64,i32,P> = model.into();
+    assert!(converted == t);
+}
+
+#[test]
+fn test_balance_policies(){
+    check_balance_policy(avl_tree::AVLTree::new());
+    check_balance_policy(avl_tree::AVLTree::with_policy(avl_tree::Wavl));
+    check_balance_policy(avl_tree::AVLTree::with_policy(avl_tree::WeightBalanced));
+}